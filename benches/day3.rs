@@ -1,10 +1,13 @@
+use aoc_2019::solvers::Solution;
 use aoc_2019::*;
 use criterion::*;
 
 fn bench_day3(c: &mut Criterion) {
+    let input = std::fs::read_to_string("input/day3.txt").unwrap();
+    let programs = day3::Day3::parse(&input);
     let mut group = c.benchmark_group("Day 3, part 1");
-    group.bench_function("2D array", move |b| b.iter(|| day3::solve_part1()));
-    group.bench_function("HashSet", move |b| b.iter(|| day3::solve_part1_with_hashset()));
+    group.bench_function("2D array", |b| b.iter(|| day3::Day3::part1(&programs)));
+    group.bench_function("HashSet", |b| b.iter(|| day3::part1_with_hashset(&programs)));
     group.finish();
 }
 
@@ -1,9 +1,12 @@
+use aoc_2019::solvers::Solution;
 use aoc_2019::*;
 use criterion::*;
 
 fn bench_day9(c: &mut Criterion) {
+    let input = std::fs::read_to_string("input/day9.txt").unwrap();
+    let program = day9::Day9::parse(&input);
     let mut group = c.benchmark_group("day 9, part 2");
-    group.bench_function("Main impl", move |b| b.iter(|| day9::solve_part2()));
+    group.bench_function("Main impl", |b| b.iter(|| day9::Day9::part2(&program)));
     group.finish();
 }
 
@@ -0,0 +1,32 @@
+//! Benchmarks parsing and both parts of every registered day on the
+//! inputs in `input/`, or the input set named by `AOC_PROFILE`.
+
+use aoc_2019::*;
+use criterion::*;
+
+fn bench_solvers(c: &mut Criterion) {
+    let set = inputs::InputSet::selected("input", None);
+    for day in solvers::days() {
+        let input = match set.read(day.day) {
+            Ok(input) => input,
+            Err(e) => {
+                eprintln!("skipping day {}: {}", day.day, e);
+                continue;
+            }
+        };
+        let parsed = day.solver.parse(&input);
+
+        let mut group = c.benchmark_group(format!("day {}", day.day));
+        group.bench_function("parse", |b| b.iter(|| day.solver.parse(black_box(&input))));
+        group.bench_function("part 1", |b| b.iter(|| day.solver.part1(parsed.as_ref())));
+        group.bench_function("part 2", |b| b.iter(|| day.solver.part2(parsed.as_ref())));
+        group.finish();
+    }
+}
+
+fn config() -> Criterion {
+    Criterion::default().sample_size(10)
+}
+
+criterion_group! {name = benches; config = config(); targets = bench_solvers}
+criterion_main!(benches);
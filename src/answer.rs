@@ -0,0 +1,223 @@
+//! Answers of puzzle parts: numbers, text, or pictures of letters, and
+//! ways to render them.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+use image::{GrayImage, Luma};
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Answer {
+    Integer(i64),
+    Text(String),
+    Bitmap(Bitmap),
+}
+
+impl Answer {
+    /// Renders the answer as a JSON object with a `type` field.
+    pub fn to_json(&self) -> String {
+        match self {
+            Answer::Integer(value) => format!("{{\"type\":\"integer\",\"value\":{}}}", value),
+            Answer::Text(text) => format!("{{\"type\":\"text\",\"value\":{}}}", json_string(text)),
+            Answer::Bitmap(bitmap) => {
+                let rows: Vec<String> = bitmap.rows().map(|row| json_string(&row)).collect();
+                format!(
+                    "{{\"type\":\"bitmap\",\"width\":{},\"height\":{},\"rows\":[{}]}}",
+                    bitmap.width,
+                    bitmap.height,
+                    rows.join(",")
+                )
+            }
+        }
+    }
+
+    /// Saves a bitmap answer as a PNG, other answers have no picture.
+    pub fn save_png<P>(&self, path: P, scale: u32) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        match self {
+            Answer::Bitmap(bitmap) => bitmap.save_png(path, scale),
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "only bitmap answers can be saved as PNG",
+            )),
+        }
+    }
+}
+
+/// `s` as a quoted JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut json = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+    json
+}
+
+/// Terminal rendering, bitmaps take a line per row.
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Answer::Integer(value) => write!(f, "{}", value),
+            Answer::Text(text) => write!(f, "{}", text),
+            Answer::Bitmap(bitmap) => write!(f, "{}", bitmap),
+        }
+    }
+}
+
+impl From<i64> for Answer {
+    fn from(value: i64) -> Self {
+        Answer::Integer(value)
+    }
+}
+
+impl From<u32> for Answer {
+    fn from(value: u32) -> Self {
+        Answer::Integer(value.into())
+    }
+}
+
+impl From<u64> for Answer {
+    fn from(value: u64) -> Self {
+        Answer::Integer(i64::try_from(value).expect("answer doesn't fit in i64"))
+    }
+}
+
+impl From<usize> for Answer {
+    fn from(value: usize) -> Self {
+        Answer::Integer(i64::try_from(value).expect("answer doesn't fit in i64"))
+    }
+}
+
+impl From<String> for Answer {
+    fn from(text: String) -> Self {
+        Answer::Text(text)
+    }
+}
+
+impl From<Bitmap> for Answer {
+    fn from(bitmap: Bitmap) -> Self {
+        Answer::Bitmap(bitmap)
+    }
+}
+
+/// Black and white picture, row major.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Bitmap {
+    pub width: usize,
+    pub height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Bitmap {
+    pub fn new(width: usize, height: usize) -> Self {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    /// Bitmap from rows of text where `#` is a lit pixel, rows are padded
+    /// to the longest one.
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let width = rows.iter().map(|r| r.chars().count()).max().unwrap_or(0);
+        let mut bitmap = Bitmap::new(width, rows.len());
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                bitmap.set(x, y, c == '#');
+            }
+        }
+        bitmap
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, lit: bool) {
+        self.pixels[y * self.width + x] = lit;
+    }
+
+    /// Rows as text, `#` for lit pixels and spaces for the rest.
+    pub fn rows(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.height).map(move |y| {
+            (0..self.width)
+                .map(|x| if self.get(x, y) { '#' } else { ' ' })
+                .collect()
+        })
+    }
+
+    /// White on black picture with every pixel blown up to a `scale` x
+    /// `scale` square.
+    pub fn to_image(&self, scale: u32) -> GrayImage {
+        GrayImage::from_fn(
+            self.width as u32 * scale,
+            self.height as u32 * scale,
+            |x, y| {
+                let lit = self.get((x / scale) as usize, (y / scale) as usize);
+                Luma([if lit { 255 } else { 0 }])
+            },
+        )
+    }
+
+    pub fn save_png<P>(&self, path: P, scale: u32) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        self.to_image(scale)
+            .save_with_format(path, image::ImageFormat::PNG)
+    }
+}
+
+impl fmt::Display for Bitmap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for row in self.rows() {
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(Answer::from(42u64).to_string(), "42");
+        assert_eq!(
+            Answer::from(-7i64).to_json(),
+            r#"{"type":"integer","value":-7}"#
+        );
+        assert_eq!(
+            Answer::from("a \"b\"\n".to_owned()).to_json(),
+            r#"{"type":"text","value":"a \"b\"\n"}"#
+        );
+
+        let bitmap = Bitmap::from_rows(&["# #", " #"]);
+        assert!(bitmap.get(0, 0) && !bitmap.get(2, 1));
+        let answer = Answer::from(bitmap.clone());
+        assert_eq!(answer.to_string(), "# #\n # \n");
+        assert_eq!(
+            answer.to_json(),
+            r##"{"type":"bitmap","width":3,"height":2,"rows":["# #"," # "]}"##
+        );
+
+        let image = bitmap.to_image(2);
+        assert_eq!(image.dimensions(), (6, 4));
+        assert_eq!(image.get_pixel(1, 1), &Luma([255]));
+        assert_eq!(image.get_pixel(3, 1), &Luma([0]));
+        assert!(Answer::Integer(1).save_png("never.png", 1).is_err());
+    }
+}
@@ -1,13 +1,23 @@
-use std::fs;
+use crate::solvers::Solution;
 
-pub fn solve_part1() -> u64 {
-    let input = fs::read_to_string("input/day1.txt").unwrap();
-    input.lines().map(|x| fuel(x.parse().unwrap())).sum()
-}
+pub struct Day1;
+
+impl Solution for Day1 {
+    type Input = Vec<u64>;
+    type Part1 = u64;
+    type Part2 = u64;
+
+    fn parse(input: &str) -> Vec<u64> {
+        input.lines().map(|x| x.parse().unwrap()).collect()
+    }
 
-pub fn solve_part2() -> u64 {
-    let input = fs::read_to_string("input/day1.txt").unwrap();
-    input.lines().map(|x| total_fuel(x.parse().unwrap())).sum()
+    fn part1(masses: &Vec<u64>) -> u64 {
+        masses.iter().map(|&mass| fuel(mass)).sum()
+    }
+
+    fn part2(masses: &Vec<u64>) -> u64 {
+        masses.iter().map(|&mass| total_fuel(mass)).sum()
+    }
 }
 
 fn fuel(mass: u64) -> u64 {
@@ -1,19 +1,28 @@
 use log::*;
 use num::Integer;
-use std::fs;
+
+use crate::solvers::Solution;
 
 type Map = Vec<Vec<char>>;
 
-pub fn solve_part1() -> usize {
-    let map_data = fs::read_to_string("input/day10.txt").unwrap();
-    let map = read_map(&map_data);
-    solve(&map).0
-}
+pub struct Day10;
+
+impl Solution for Day10 {
+    type Input = Map;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Map {
+        read_map(input)
+    }
+
+    fn part1(map: &Map) -> usize {
+        solve(map).0
+    }
 
-pub fn solve_part2() -> usize {
-    let map_data = fs::read_to_string("input/day10.txt").unwrap();
-    let mut map = read_map(&map_data);
-    solve2(&mut map).unwrap()
+    fn part2(map: &Map) -> usize {
+        solve2(&mut map.clone()).unwrap()
+    }
 }
 
 fn read_map(s: &str) -> Map {
@@ -1,67 +1,75 @@
+use crate::answer::{Answer, Bitmap};
 use crate::intcode::*;
+use crate::ocr;
+use crate::solvers::Solution;
 
+use std::cell::Cell;
+use std::cell::RefCell;
 use std::collections::HashMap;
 
-use image::ImageBuffer;
+pub struct Day11;
 
-pub fn solve_part1() -> usize {
-    let mut comp = Computer::new();
-    comp.load_from_file("input/day11.txt");
-    let mut hull = Hull::new();
-    run_robot(&mut comp, &mut hull, Position { x: 0, y: 0 }, Dir::Up);
-    hull.painted_plates()
-}
+impl Solution for Day11 {
+    type Input = Vec<i64>;
+    type Part1 = usize;
+    type Part2 = Answer;
+
+    fn parse(input: &str) -> Vec<i64> {
+        Computer::parse_program(input)
+    }
+
+    fn part1(program: &Vec<i64>) -> usize {
+        let mut comp = Computer::new();
+        comp.load_memory(program);
+        let mut hull = Hull::new();
+        run_robot(&mut comp, &mut hull, Position { x: 0, y: 0 }, Dir::Up);
+        hull.painted_plates()
+    }
 
-pub fn solve_part2() {
-    let mut comp = Computer::new();
-    comp.load_from_file("input/day11.txt");
+    fn part2(program: &Vec<i64>) -> Answer {
+        let mut comp = Computer::new();
+        comp.load_memory(program);
 
-    let mut hull = Hull::new();
-    let pos = Position { x: 0, y: 0 };
-    hull.paint(&pos, Color::White);
+        let mut hull = Hull::new();
+        let pos = Position { x: 0, y: 0 };
+        hull.paint(&pos, Color::White);
 
-    run_robot(&mut comp, &mut hull, pos, Dir::Up);
-    draw(hull);
+        run_robot(&mut comp, &mut hull, pos, Dir::Up);
+        ocr::answer(draw(hull))
+    }
 }
 
-fn draw(hull: Hull) {
+/// The painted part of the hull, white plates are lit.
+fn draw(hull: Hull) -> Bitmap {
     let pixels = hull.pixels();
 
     let min_x = pixels.iter().map(|&p| p.0.x).min().unwrap();
     let min_y = pixels.iter().map(|&p| p.0.y).min().unwrap();
     let max_x = pixels.iter().map(|&p| p.0.x).max().unwrap();
     let max_y = pixels.iter().map(|&p| p.0.y).max().unwrap();
-    let width = max_x - min_x + 1;
-    let height = max_y - min_y + 1;
-
-    let mut img = ImageBuffer::new(width as u32, height as u32);
+    let width = (max_x - min_x + 1) as usize;
+    let height = (max_y - min_y + 1) as usize;
 
-    for pix in pixels {
-        let c = match pix.1 {
-            Color::Black => image::Luma([0]),
-            Color::White => image::Luma([255]),
-        };
-        img.put_pixel(pix.0.x as u32, pix.0.y as u32, c);
+    let mut bitmap = Bitmap::new(width, height);
+    for (pos, color) in pixels {
+        let (x, y) = ((pos.x - min_x) as usize, (pos.y - min_y) as usize);
+        bitmap.set(x, y, color == Color::White);
     }
-
-    img.save("plate.png").unwrap();
+    bitmap
 }
 
-fn run_robot(comp: &mut Computer, hull: &mut Hull, mut pos: Position, mut dir: Dir) {
-    loop {
-        let color_below = hull.look(&pos);
-        comp.run_as_coroutine();
-        match comp.status {
-            Status::Halted => break,
-            Status::RequiresInput => comp.send_input(color_below as i64),
-            Status::ProducedOutput(_) => panic!("unexpected output"),
-        }
-        let paint_in = Color::from(comp.peek_output().unwrap());
-        comp.run_as_coroutine();
-        let turn = Turn::from(comp.peek_output().unwrap());
-        hull.paint(&pos, paint_in);
-        dir = dir.turn(&turn);
-        pos.go(&dir);
+fn run_robot(comp: &mut Computer, hull: &mut Hull, pos: Position, mut dir: Dir) {
+    let hull = RefCell::new(hull);
+    let pos = Cell::new(pos);
+    let camera = || hull.borrow().look(&pos.get()) as i64;
+
+    for command in comp.outputs_with_input(camera).pairs() {
+        let (paint, turn) = command.unwrap_or_else(|fault| panic!("robot faulted: {}", fault));
+        let mut next_pos = pos.get();
+        hull.borrow_mut().paint(&next_pos, Color::from(paint));
+        dir = dir.turn(&Turn::from(turn));
+        next_pos.go(&dir);
+        pos.set(next_pos);
     }
 }
 
@@ -1,41 +1,42 @@
 use num::Integer;
 use std::cmp::Ordering;
-use std::fs;
 
-pub fn solve_part1() -> i64 {
-    let input = fs::read_to_string("input/day12.txt").unwrap();
-    let mut system = System::from_str(&input);
-    system.run(1000);
-    system.total_energy()
-}
-
-pub fn solve_part2() -> usize {
-    let input = fs::read_to_string("input/day12.txt").unwrap();
+use crate::solvers::Solution;
 
-    let mut xs = vec![];
-    let mut ys = vec![];
-    let mut zs = vec![];
+pub struct Day12;
 
-    for moon_str in input.lines() {
-        let parts: Vec<&str> = moon_str
-            .trim_matches(|c| c == '<' || c == '>')
-            .split(",")
-            .collect();
+impl Solution for Day12 {
+    type Input = Vec<Moon>;
+    type Part1 = i64;
+    type Part2 = usize;
 
-        fn parse_coord(parts: &Vec<&str>, ix: usize) -> i64 {
-            parts[ix].trim().split("=").nth(1).unwrap().parse().unwrap()
-        }
+    fn parse(input: &str) -> Vec<Moon> {
+        parse_moons(input)
+    }
 
-        xs.push(MovingScalar::new(parse_coord(&parts, 0)));
-        ys.push(MovingScalar::new(parse_coord(&parts, 1)));
-        zs.push(MovingScalar::new(parse_coord(&parts, 2)));
+    fn part1(moons: &Vec<Moon>) -> i64 {
+        let mut system = System::new(moons.clone());
+        system.run(1000);
+        system.total_energy()
     }
 
-    let x_cycle = System::new(xs).cycle_len();
-    let y_cycle = System::new(ys).cycle_len();
-    let z_cycle = System::new(zs).cycle_len();
+    fn part2(moons: &Vec<Moon>) -> usize {
+        let axis = |position: fn(&Moon) -> i64| {
+            moons
+                .iter()
+                .map(|m| MovingScalar::new(position(m)))
+                .collect::<Vec<_>>()
+        };
+        let xs = axis(|m| m.x);
+        let ys = axis(|m| m.y);
+        let zs = axis(|m| m.z);
+
+        let x_cycle = System::new(xs).cycle_len();
+        let y_cycle = System::new(ys).cycle_len();
+        let z_cycle = System::new(zs).cycle_len();
 
-    x_cycle.lcm(&y_cycle).lcm(&z_cycle)
+        x_cycle.lcm(&y_cycle).lcm(&z_cycle)
+    }
 }
 
 pub trait InGravity {
@@ -88,27 +89,32 @@ pub struct System<T> {
     objects: Vec<T>,
 }
 
-impl System<Moon> {
-    pub fn from_str(input: &str) -> System<Moon> {
-        let mut moons = vec![];
-        for moon_str in input.lines() {
-            let parts: Vec<&str> = moon_str
-                .trim_matches(|c| c == '<' || c == '>')
-                .split(",")
-                .collect();
-
-            fn parse_coord(parts: &Vec<&str>, ix: usize) -> i64 {
-                parts[ix].trim().split("=").nth(1).unwrap().parse().unwrap()
-            }
-
-            let x = parse_coord(&parts, 0);
-            let y = parse_coord(&parts, 1);
-            let z = parse_coord(&parts, 2);
+/// Moons at their starting positions, not moving yet.
+fn parse_moons(input: &str) -> Vec<Moon> {
+    let mut moons = vec![];
+    for moon_str in input.lines() {
+        let parts: Vec<&str> = moon_str
+            .trim()
+            .trim_matches(|c| c == '<' || c == '>')
+            .split(",")
+            .collect();
 
-            moons.push(Moon::new(x, y, z));
+        fn parse_coord(parts: &Vec<&str>, ix: usize) -> i64 {
+            parts[ix].trim().split("=").nth(1).unwrap().parse().unwrap()
         }
 
-        System::new(moons)
+        let x = parse_coord(&parts, 0);
+        let y = parse_coord(&parts, 1);
+        let z = parse_coord(&parts, 2);
+
+        moons.push(Moon::new(x, y, z));
+    }
+    moons
+}
+
+impl System<Moon> {
+    pub fn from_str(input: &str) -> System<Moon> {
+        System::new(parse_moons(input))
     }
 
     pub fn total_energy(&self) -> i64 {
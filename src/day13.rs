@@ -1,7 +1,14 @@
 use crate::intcode::*;
+use crate::solvers::Solution;
 
-#[derive(PartialEq)]
-enum Tile {
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io;
+use std::io::BufRead;
+
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Tile {
     Empty = 0,
     Wall = 1,
     Block = 2,
@@ -24,19 +31,210 @@ impl From<i64> for Tile {
     }
 }
 
-pub fn solve_part1() -> usize {
-    let mut comp = Computer::new();
-    comp.load_from_file("input/day13.txt");
-    comp.run();
+#[derive(PartialEq, Eq, Clone, Copy, Debug)]
+pub enum Joystick {
+    Left = -1,
+    Neutral = 0,
+    Right = 1,
+}
+
+pub struct Day13;
+
+impl Solution for Day13 {
+    type Input = Vec<i64>;
+    type Part1 = usize;
+    type Part2 = i64;
+
+    fn parse(input: &str) -> Vec<i64> {
+        Computer::parse_program(input)
+    }
+
+    fn part1(program: &Vec<i64>) -> usize {
+        let mut comp = Computer::new();
+        comp.load_memory(program);
+
+        comp.outputs()
+            .triples()
+            .map(|cmd| cmd.expect("arcade faulted"))
+            .filter(|&(_x, _y, tile)| Tile::from(tile) == Tile::Block)
+            .count()
+    }
+
+    fn part2(program: &Vec<i64>) -> i64 {
+        let mut arcade = Arcade::new(program);
+        arcade.insert_quarters();
+        arcade.run(autopilot).expect("arcade faulted")
+    }
+}
+
+/// Plays the game in the terminal, reading joystick moves from stdin:
+/// `a` or `h` is left, `d` or `l` is right, anything else keeps the
+/// joystick neutral.
+pub fn play(program: &[i64]) -> i64 {
+    let mut arcade = Arcade::new(program);
+    arcade.insert_quarters();
+
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    let score = arcade
+        .run(|screen| {
+            // Clear the terminal and move cursor to the top left corner.
+            print!("\x1b[2J\x1b[H{}", screen);
+            match lines.next().map(|l| l.unwrap_or_default()).as_deref() {
+                Some("a") | Some("h") => Joystick::Left,
+                Some("d") | Some("l") => Joystick::Right,
+                _ => Joystick::Neutral,
+            }
+        })
+        .expect("arcade faulted");
+    print!("\x1b[2J\x1b[H{}", arcade.screen());
+    score
+}
+
+/// Moves the paddle towards the ball.
+pub fn autopilot(screen: &Screen) -> Joystick {
+    match (screen.ball, screen.paddle) {
+        (Some(ball), Some(paddle)) if ball.0 < paddle.0 => Joystick::Left,
+        (Some(ball), Some(paddle)) if ball.0 > paddle.0 => Joystick::Right,
+        _ => Joystick::Neutral,
+    }
+}
+
+#[derive(Default)]
+pub struct Screen {
+    tiles: HashMap<(i64, i64), Tile>,
+    score: i64,
+    ball: Option<(i64, i64)>,
+    paddle: Option<(i64, i64)>,
+}
+
+impl Screen {
+    pub fn draw(&mut self, x: i64, y: i64, value: i64) {
+        if (x, y) == (-1, 0) {
+            self.score = value;
+            return;
+        }
+
+        let tile = Tile::from(value);
+        match tile {
+            Tile::Ball => self.ball = Some((x, y)),
+            Tile::Paddle => self.paddle = Some((x, y)),
+            _ => {}
+        }
+        self.tiles.insert((x, y), tile);
+    }
 
-    fn is_block(s: &str) -> bool {
-        Tile::from(s.parse::<i64>().unwrap()) == Tile::Block
+    pub fn score(&self) -> i64 {
+        self.score
     }
 
-    comp.output
-        .iter()
-        .skip(2)
-        .step_by(3)
-        .filter(|s| is_block(s))
-        .count()
+    pub fn ball(&self) -> Option<(i64, i64)> {
+        self.ball
+    }
+
+    pub fn tile(&self, x: i64, y: i64) -> Tile {
+        self.tiles.get(&(x, y)).cloned().unwrap_or(Tile::Empty)
+    }
+
+    pub fn blocks(&self) -> usize {
+        self.tiles.values().filter(|&&t| t == Tile::Block).count()
+    }
+
+    /// Width and height of the drawn area, screen always starts at (0, 0).
+    pub fn size(&self) -> (i64, i64) {
+        let width = self.tiles.keys().map(|p| p.0 + 1).max().unwrap_or(0);
+        let height = self.tiles.keys().map(|p| p.1 + 1).max().unwrap_or(0);
+        (width, height)
+    }
+}
+
+impl fmt::Display for Screen {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Score: {}", self.score)?;
+        let (width, height) = self.size();
+        for y in 0..height {
+            for x in 0..width {
+                let c = match self.tile(x, y) {
+                    Tile::Empty => ' ',
+                    Tile::Wall => '#',
+                    Tile::Block => '=',
+                    Tile::Paddle => '-',
+                    Tile::Ball => 'o',
+                };
+                write!(f, "{}", c)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+pub struct Arcade {
+    comp: Computer,
+    screen: Screen,
+}
+
+impl Arcade {
+    pub fn new(program: &[i64]) -> Self {
+        let mut comp = Computer::new();
+        comp.load_memory(program);
+        Arcade {
+            comp,
+            screen: Screen::default(),
+        }
+    }
+
+    /// Sets memory address 0 to 2, so the game can be played for free.
+    pub fn insert_quarters(&mut self) {
+        let mut mem = self.comp.dump_memory().to_vec();
+        mem[0] = 2;
+        self.comp.load_memory(mem);
+    }
+
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    /// Runs the game until it halts, asking `joystick` for the next move
+    /// every time the game waits for input. Returns the final score.
+    pub fn run<F>(&mut self, mut joystick: F) -> Result<i64, Fault>
+    where
+        F: FnMut(&Screen) -> Joystick,
+    {
+        let screen = RefCell::new(&mut self.screen);
+        let input = || joystick(&screen.borrow()) as i64;
+        for cmd in self.comp.outputs_with_input(input).triples() {
+            let (x, y, value) = cmd?;
+            screen.borrow_mut().draw(x, y, value);
+        }
+        Ok(self.screen.score)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_screen() {
+        let mut screen = Screen::default();
+        for &(x, y, v) in &[
+            (0, 0, 1),
+            (1, 0, 2),
+            (2, 0, 1),
+            (1, 1, 4),
+            (1, 2, 3),
+            (-1, 0, 17),
+        ] {
+            screen.draw(x, y, v);
+        }
+        assert_eq!(screen.score(), 17);
+        assert_eq!(screen.blocks(), 1);
+        assert_eq!(screen.to_string(), "Score: 17\n#=#\n o \n - \n");
+        assert_eq!(autopilot(&screen), Joystick::Neutral);
+
+        screen.draw(1, 1, 0);
+        screen.draw(2, 1, 4);
+        assert_eq!(autopilot(&screen), Joystick::Right);
+    }
 }
@@ -0,0 +1,207 @@
+use crate::day13::*;
+
+use std::fs::File;
+use std::path::Path;
+
+use image::gif::Encoder;
+use image::{Frame, ImageResult, Rgba, RgbaImage};
+use num::rational::Ratio;
+
+const FONT_WIDTH: u32 = 3;
+const FONT_HEIGHT: u32 = 5;
+
+// 3x5 digits, every row is 3 bits, the most significant one is the
+// leftmost pixel.
+const DIGITS: [[u8; 5]; 10] = [
+    [0b111, 0b101, 0b101, 0b101, 0b111],
+    [0b010, 0b110, 0b010, 0b010, 0b111],
+    [0b111, 0b001, 0b111, 0b100, 0b111],
+    [0b111, 0b001, 0b111, 0b001, 0b111],
+    [0b101, 0b101, 0b111, 0b001, 0b001],
+    [0b111, 0b100, 0b111, 0b001, 0b111],
+    [0b111, 0b100, 0b111, 0b101, 0b111],
+    [0b111, 0b001, 0b001, 0b001, 0b001],
+    [0b111, 0b101, 0b111, 0b101, 0b111],
+    [0b111, 0b101, 0b111, 0b001, 0b111],
+];
+
+const BACKGROUND: Rgba<u8> = Rgba([0, 0, 0, 255]);
+const SCORE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+fn color(tile: Tile) -> Rgba<u8> {
+    match tile {
+        Tile::Empty => BACKGROUND,
+        Tile::Wall => Rgba([110, 110, 110, 255]),
+        Tile::Block => Rgba([230, 140, 30, 255]),
+        Tile::Paddle => Rgba([60, 160, 255, 255]),
+        Tile::Ball => Rgba([240, 40, 40, 255]),
+    }
+}
+
+/// Renders the screen with every tile as a `scale` x `scale` square and
+/// the score drawn in a band above the playing field.
+pub fn render(screen: &Screen, scale: u32) -> RgbaImage {
+    let (width, height) = screen.size();
+    let dot = (scale / 2).max(1);
+    let band = (FONT_HEIGHT + 2) * dot;
+
+    let mut img = RgbaImage::from_pixel(
+        width as u32 * scale,
+        height as u32 * scale + band,
+        BACKGROUND,
+    );
+    for y in 0..height {
+        for x in 0..width {
+            let c = color(screen.tile(x, y));
+            if c != BACKGROUND {
+                fill(
+                    &mut img,
+                    x as u32 * scale,
+                    band + y as u32 * scale,
+                    scale,
+                    c,
+                );
+            }
+        }
+    }
+
+    let digits = screen.score().to_string();
+    for (ix, digit) in digits.chars().filter_map(|c| c.to_digit(10)).enumerate() {
+        let left = dot + ix as u32 * (FONT_WIDTH + 1) * dot;
+        for (row, bits) in DIGITS[digit as usize].iter().enumerate() {
+            for col in 0..FONT_WIDTH {
+                if bits & (1 << (FONT_WIDTH - 1 - col)) != 0 {
+                    fill(
+                        &mut img,
+                        left + col * dot,
+                        dot + row as u32 * dot,
+                        dot,
+                        SCORE,
+                    );
+                }
+            }
+        }
+    }
+
+    img
+}
+
+fn fill(img: &mut RgbaImage, left: u32, top: u32, size: u32, c: Rgba<u8>) {
+    for y in top..(top + size).min(img.height()) {
+        for x in left..(left + size).min(img.width()) {
+            img.put_pixel(x, y, c);
+        }
+    }
+}
+
+/// Collects rendered frames of the arcade screen, a new frame is taken
+/// every time the ball moves.
+pub struct FrameRecorder {
+    scale: u32,
+    last_ball: Option<(i64, i64)>,
+    frames: Vec<RgbaImage>,
+}
+
+impl FrameRecorder {
+    pub fn new(scale: u32) -> Self {
+        FrameRecorder {
+            scale,
+            last_ball: None,
+            frames: vec![],
+        }
+    }
+
+    pub fn observe(&mut self, screen: &Screen) {
+        if screen.ball() != self.last_ball {
+            self.capture(screen);
+        }
+    }
+
+    pub fn capture(&mut self, screen: &Screen) {
+        self.last_ball = screen.ball();
+        self.frames.push(render(screen, self.scale));
+    }
+
+    pub fn frames(&self) -> &[RgbaImage] {
+        &self.frames
+    }
+
+    /// Saves frames as `frame00000.png`, `frame00001.png`, ... in `dir`.
+    pub fn save_png_sequence<P>(&self, dir: P) -> ImageResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        for (ix, frame) in self.frames.iter().enumerate() {
+            frame.save(dir.as_ref().join(format!("frame{:05}.png", ix)))?;
+        }
+        Ok(())
+    }
+
+    /// Saves every `step`-th frame as an animated GIF, showing each one
+    /// for `delay` hundredths of a second. Palette is computed per
+    /// frame, so long recordings take a while to encode.
+    pub fn save_gif<P>(&self, path: P, step: usize, delay: u16) -> ImageResult<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut encoder = Encoder::new(File::create(path)?);
+        let frames = self
+            .frames
+            .iter()
+            .step_by(step.max(1))
+            .map(|img| Frame::from_parts(img.clone(), 0, 0, Ratio::from_integer(delay)));
+        encoder.encode_frames(frames)
+    }
+}
+
+/// Plays the game with the autopilot, recording it.
+pub fn record_autopilot(program: &[i64], scale: u32) -> FrameRecorder {
+    let mut arcade = Arcade::new(program);
+    arcade.insert_quarters();
+
+    let mut recorder = FrameRecorder::new(scale);
+    arcade
+        .run(|screen| {
+            recorder.observe(screen);
+            autopilot(screen)
+        })
+        .expect("arcade faulted");
+    recorder.capture(arcade.screen());
+    recorder
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        let mut screen = Screen::default();
+        screen.draw(0, 0, 1);
+        screen.draw(1, 1, 4);
+        screen.draw(-1, 0, 7);
+
+        let img = render(&screen, 4);
+        let band = (FONT_HEIGHT + 2) * 2;
+        assert_eq!(img.dimensions(), (8, 8 + band));
+        assert_eq!(*img.get_pixel(0, band), color(Tile::Wall));
+        assert_eq!(*img.get_pixel(5, band + 5), color(Tile::Ball));
+        assert_eq!(*img.get_pixel(4, band), BACKGROUND);
+        // Top row of digit 7 is fully lit.
+        assert_eq!(*img.get_pixel(2, 2), SCORE);
+        assert_eq!(*img.get_pixel(6, 2), SCORE);
+    }
+
+    #[test]
+    fn test_recorder() {
+        let mut screen = Screen::default();
+        screen.draw(1, 1, 4);
+
+        let mut recorder = FrameRecorder::new(1);
+        recorder.observe(&screen);
+        recorder.observe(&screen);
+        screen.draw(2, 1, 4);
+        recorder.observe(&screen);
+        assert_eq!(recorder.frames().len(), 2);
+    }
+}
@@ -1,4 +1,4 @@
-use std::fs;
+use crate::solvers::Solution;
 
 enum OpCode {
     Add,
@@ -17,44 +17,48 @@ impl From<u64> for OpCode {
     }
 }
 
-pub fn solve_part1() -> u64 {
-    let program = fs::read_to_string("input/day2.txt").unwrap();
-    let mut codes: Vec<u64> = program
-        .trim()
-        .split(",")
-        .map(|x| x.parse().unwrap())
-        .collect();
-    codes[1] = 12;
-    codes[2] = 2;
-    run(&mut codes);
-    codes[0]
-}
+pub struct Day2;
+
+impl Solution for Day2 {
+    type Input = Vec<u64>;
+    type Part1 = u64;
+    type Part2 = u64;
 
-pub fn solve_part2() -> u64 {
-    let program = fs::read_to_string("input/day2.txt").unwrap();
-    let orig_codes: Vec<u64> = program
-        .trim()
-        .split(",")
-        .map(|x| x.parse().unwrap())
-        .collect();
-
-    const TARGET: u64 = 19690720;
-
-    for noun in 0..99 {
-        for verb in 0..99 {
-            let mut codes = orig_codes.clone();
-            codes[1] = noun;
-            codes[2] = verb;
-            run(&mut codes);
-            let output = codes[0];
-            if output == TARGET {
-                let result = noun * 100 + verb;
-                return result;
+    fn parse(input: &str) -> Vec<u64> {
+        input
+            .trim()
+            .split(",")
+            .map(|x| x.parse().unwrap())
+            .collect()
+    }
+
+    fn part1(orig_codes: &Vec<u64>) -> u64 {
+        let mut codes = orig_codes.clone();
+        codes[1] = 12;
+        codes[2] = 2;
+        run(&mut codes);
+        codes[0]
+    }
+
+    fn part2(orig_codes: &Vec<u64>) -> u64 {
+        const TARGET: u64 = 19690720;
+
+        for noun in 0..99 {
+            for verb in 0..99 {
+                let mut codes = orig_codes.clone();
+                codes[1] = noun;
+                codes[2] = verb;
+                run(&mut codes);
+                let output = codes[0];
+                if output == TARGET {
+                    let result = noun * 100 + verb;
+                    return result;
+                }
             }
         }
-    }
 
-    0
+        0
+    }
 }
 
 fn run(data: &mut Vec<u64>) {
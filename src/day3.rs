@@ -4,6 +4,8 @@ use std::str::FromStr;
 
 use itertools::Itertools;
 
+use crate::solvers::Solution;
+
 type Program<'a> = &'a [Move];
 
 pub enum Move {
@@ -99,40 +101,40 @@ impl Frame {
     }
 }
 
-pub fn solve_part1() -> u32 {
-    let data = std::fs::read_to_string("input/day3.txt").unwrap();
-    let programs: Vec<_> = data.lines().map(mk_program).collect();
+pub struct Day3;
 
-    let frame = Frame::new_from_list(&programs);
-    let mut bitmap: Vec<u8> = vec![0; (frame.width * frame.height) as usize];
+impl Solution for Day3 {
+    type Input = Vec<Vec<Move>>;
+    type Part1 = u32;
+    type Part2 = u32;
 
-    mark_path_with_val(&mut bitmap, &frame, 1, &programs[0]);
-    let dist = follow_path_with_min_dist(&mut bitmap, &frame, &programs[1]);
+    fn parse(input: &str) -> Vec<Vec<Move>> {
+        input.lines().map(mk_program).collect()
+    }
 
-    // println!("day 3, part 1: {}", dist);
-    dist
-}
+    fn part1(programs: &Vec<Vec<Move>>) -> u32 {
+        let frame = Frame::new_from_list(programs);
+        let mut bitmap: Vec<u8> = vec![0; (frame.width * frame.height) as usize];
 
-pub fn solve_part1_with_hashset() -> u32 {
-    let data = std::fs::read_to_string("input/day3.txt").unwrap();
-    let programs: Vec<_> = data.lines().map(mk_program).collect();
+        mark_path_with_val(&mut bitmap, &frame, 1, &programs[0]);
+        follow_path_with_min_dist(&mut bitmap, &frame, &programs[1])
+    }
 
-    let frame = Frame::new_from_list(&programs);
+    fn part2(programs: &Vec<Vec<Move>>) -> u32 {
+        let frame = Frame::new_from_list(programs);
+        let mut bitmap: Vec<u32> = vec![0; (frame.width * frame.height) as usize];
 
-    let mut sparse = HashSet::with_capacity(128_000);
-    mark_path_with_val_alt(&mut sparse, &frame, &programs[0]);
-    follow_path_with_min_dist_alt(&mut sparse, &frame, &programs[1])
+        mark_path_with_dist(&mut bitmap, &frame, &programs[0]);
+        follow_path_with_total_steps(&mut bitmap, &frame, &programs[1])
+    }
 }
 
-pub fn solve_part2() -> u32 {
-    let data = std::fs::read_to_string("input/day3.txt").unwrap();
-    let programs: Vec<_> = data.lines().map(mk_program).collect();
-
-    let frame = Frame::new_from_list(&programs);
-    let mut bitmap: Vec<u32> = vec![0; (frame.width * frame.height) as usize];
+pub fn part1_with_hashset(programs: &Vec<Vec<Move>>) -> u32 {
+    let frame = Frame::new_from_list(programs);
 
-    mark_path_with_dist(&mut bitmap, &frame, &programs[0]);
-    follow_path_with_total_steps(&mut bitmap, &frame, &programs[1])
+    let mut sparse = HashSet::with_capacity(128_000);
+    mark_path_with_val_alt(&mut sparse, &frame, &programs[0]);
+    follow_path_with_min_dist_alt(&mut sparse, &frame, &programs[1])
 }
 
 fn mk_program(s: &str) -> Vec<Move> {
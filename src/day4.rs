@@ -1,13 +1,27 @@
-pub fn solve_part1() -> usize {
-    let start = 152085;
-    let end = 670283;
-    (start..=end).filter(is_good).count()
-}
+use std::ops::RangeInclusive;
+
+use crate::solvers::Solution;
+
+pub struct Day4;
 
-pub fn solve_part2() -> usize {
-    let start = 152085;
-    let end = 670283;
-    (start..=end).filter(is_good_2).count()
+impl Solution for Day4 {
+    type Input = RangeInclusive<u32>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    /// The input is the range of candidates, e.g. `152085-670283`.
+    fn parse(input: &str) -> RangeInclusive<u32> {
+        let (start, end) = input.trim().split_once('-').expect("expected start-end");
+        start.parse().unwrap()..=end.parse().unwrap()
+    }
+
+    fn part1(range: &RangeInclusive<u32>) -> usize {
+        range.clone().filter(is_good).count()
+    }
+
+    fn part2(range: &RangeInclusive<u32>) -> usize {
+        range.clone().filter(is_good_2).count()
+    }
 }
 
 fn is_good(cand: &u32) -> bool {
@@ -1,17 +1,30 @@
 use crate::intcode::*;
+use crate::solvers::Solution;
 
-pub fn solve_part1() -> u32 {
-    let mut comp = Computer::new();
-    comp.load_from_file("input/day5.txt");
-    comp.set_input_lines(&["1"]);
-    comp.run();
-    comp.last_output().parse().unwrap()
+pub struct Day5;
+
+impl Solution for Day5 {
+    type Input = Vec<i64>;
+    type Part1 = u32;
+    type Part2 = u32;
+
+    fn parse(input: &str) -> Vec<i64> {
+        Computer::parse_program(input)
+    }
+
+    fn part1(program: &Vec<i64>) -> u32 {
+        run_with_input(program, "1")
+    }
+
+    fn part2(program: &Vec<i64>) -> u32 {
+        run_with_input(program, "5")
+    }
 }
 
-pub fn solve_part2() -> u32 {
+fn run_with_input(program: &[i64], input: &str) -> u32 {
     let mut comp = Computer::new();
-    comp.load_from_file("input/day5.txt");
-    comp.set_input_lines(&["5"]);
+    comp.load_memory(program);
+    comp.set_input_lines(&[input]);
     comp.run();
     comp.last_output().parse().unwrap()
 }
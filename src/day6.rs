@@ -1,43 +1,59 @@
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
-use std::fs;
+
+use crate::solvers::Solution;
 
 type OrbitalMap = HashMap<String, Vec<String>>;
 
-pub fn solve_part1() -> usize {
-    let orbital_map_content = fs::read_to_string("input/day6.txt").unwrap();
-    let orbital_map = to_directed_graph(&orbital_map_content);
+/// Orbit centre and the object orbiting it.
+type Orbit = (String, String);
 
-    solve(&orbital_map, "COM")
-}
+pub struct Day6;
+
+impl Solution for Day6 {
+    type Input = Vec<Orbit>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(input: &str) -> Vec<Orbit> {
+        parse_orbits(input)
+    }
+
+    fn part1(orbits: &Vec<Orbit>) -> usize {
+        let orbital_map = to_directed_graph(orbits);
 
-pub fn solve_part2() -> usize {
-    let orbital_map_content = fs::read_to_string("input/day6.txt").unwrap();
-    let orbital_map = to_undirected_graph(&orbital_map_content);
+        solve(&orbital_map, "COM")
+    }
+
+    fn part2(orbits: &Vec<Orbit>) -> usize {
+        let orbital_map = to_undirected_graph(orbits);
+
+        min_distance(&orbital_map, "YOU", "SAN").unwrap() - 2
+    }
+}
 
-    min_distance(&orbital_map, "YOU", "SAN").unwrap() - 2
+fn parse_orbits(data: &str) -> Vec<Orbit> {
+    data.split_whitespace()
+        .map(|line| {
+            let parts: Vec<_> = line.trim().split(")").collect();
+            assert!(parts.len() == 2);
+            (String::from(parts[0]), String::from(parts[1]))
+        })
+        .collect()
 }
 
-fn to_directed_graph(data: &str) -> OrbitalMap {
+fn to_directed_graph(orbits: &[Orbit]) -> OrbitalMap {
     let mut orbital_map: OrbitalMap = HashMap::new();
-    for line in data.split_whitespace() {
-        let parts: Vec<_> = line.trim().split(")").collect();
-        assert!(parts.len() == 2);
-        let orbit_centre = String::from(parts[0]);
-        let orbitant = String::from(parts[1]);
+    for (orbit_centre, orbitant) in orbits.iter().cloned() {
         orbital_map.entry(orbit_centre).or_default().push(orbitant);
     }
     orbital_map
 }
 
-fn to_undirected_graph(data: &str) -> OrbitalMap {
+fn to_undirected_graph(orbits: &[Orbit]) -> OrbitalMap {
     let mut orbital_map: OrbitalMap = HashMap::new();
-    for line in data.split_whitespace() {
-        let parts: Vec<_> = line.trim().split(")").collect();
-        assert!(parts.len() == 2);
-        let orbit_centre = String::from(parts[0]);
-        let orbitant = String::from(parts[1]);
+    for (orbit_centre, orbitant) in orbits.iter().cloned() {
         orbital_map
             .entry(orbit_centre.clone())
             .or_default()
@@ -98,7 +114,7 @@ mod tests {
     #[test]
     fn test() {
         let input = "COM)B B)C C)D D)E E)F B)G G)H D)I E)J J)K K)L";
-        let map = to_directed_graph(&input);
+        let map = to_directed_graph(&parse_orbits(input));
         assert_eq!(solve(&map, "K"), 1);
         assert_eq!(solve(&map, "L"), 0);
         assert_eq!(solve(&map, "J"), 3);
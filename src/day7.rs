@@ -1,14 +1,29 @@
 use crate::intcode::*;
+use crate::solvers::Solution;
 use permutohedron::Heap;
 
-pub fn solve_part1() -> i64 {
-    let memory = Computer::read_program("input/day7.txt");
-    enumerate_phases(&memory)
-}
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+
+pub struct Day7;
+
+impl Solution for Day7 {
+    type Input = Vec<i64>;
+    type Part1 = i64;
+    type Part2 = i64;
+
+    fn parse(input: &str) -> Vec<i64> {
+        Computer::parse_program(input)
+    }
 
-pub fn solve_part2() -> i64 {
-    let memory = Computer::read_program("input/day7.txt");
-    enumerate_phases_2(&memory)
+    fn part1(memory: &Vec<i64>) -> i64 {
+        enumerate_phases(memory)
+    }
+
+    fn part2(memory: &Vec<i64>) -> i64 {
+        enumerate_phases_2(memory)
+    }
 }
 
 fn enumerate_phases(mem: &[i64]) -> i64 {
@@ -24,10 +39,12 @@ fn run_pipeline(permutation: &[i64], mem: &[i64]) -> i64 {
     for ix in 0..5 {
         let mut comp = Computer::new();
         comp.load_memory(mem);
-        comp.run_as_coroutine();
-        comp.send_input(permutation[ix]);
-        comp.send_input(out);
-        out = comp.peek_output().unwrap();
+        let mut inputs = vec![permutation[ix], out].into_iter();
+        out = match comp.outputs_with_input(|| inputs.next().unwrap()).next() {
+            Some(Ok(out)) => out,
+            Some(Err(fault)) => panic!("amplifier faulted: {}", fault),
+            None => panic!("amplifier halted without output"),
+        };
     }
     out
 }
@@ -41,58 +58,45 @@ fn enumerate_phases_2(mem: &[i64]) -> i64 {
 }
 
 fn run_feedback_loop(permutation: &[i64], mem: &[i64]) -> i64 {
-    let mut farm = [
-        Computer::new(),
-        Computer::new(),
-        Computer::new(),
-        Computer::new(),
-        Computer::new(),
-    ];
-
+    let mut farm: Vec<Computer> = (0..5)
+        .map(|_| {
+            let mut comp = Computer::new();
+            comp.load_memory(mem);
+            comp
+        })
+        .collect();
+
+    // Every amplifier reads its phase first and then signals from the
+    // previous one, which are queued right before it's asked for the
+    // next output.
+    let queues: Vec<Rc<RefCell<VecDeque<i64>>>> = permutation
+        .iter()
+        .map(|&phase| Rc::new(RefCell::new(VecDeque::from(vec![phase]))))
+        .collect();
+    let mut amplifiers: Vec<_> = farm
+        .iter_mut()
+        .zip(queues.iter())
+        .map(|(comp, queue)| {
+            let queue = Rc::clone(queue);
+            comp.outputs_with_input(move || queue.borrow_mut().pop_front().unwrap())
+        })
+        .collect();
+
+    // Feedback loop. Amplifiers take turns ("round robin" style), each
+    // one turning a signal into the next, until the first one halts
+    // instead of producing output. The last signal is then the one from
+    // the final amplifier.
     let mut signal: i64 = 0;
-
-    // "Priming" computers/coroutines.
-    for ix in 0..5 {
-        let comp = &mut farm[ix];
-        comp.load_memory(mem);
-        comp.run_as_coroutine();
-        comp.send_input(permutation[ix]);
-        comp.send_input(signal);
-        signal = comp.peek_output().unwrap();
-    }
-
-    // Feedback loop. We schedule coroutings sequentially ("round
-    // robin" style) and just use a single `signal` variable to pass
-    // around signals. Also, we keep track of halted coroutines and
-    // terminate once all coroutings halted.
-    let mut halted = [false; 5];
     loop {
-        for ix in 0..5 {
-            let comp = &mut farm[ix];
-            comp.run_as_coroutine();
-            match comp.status {
-                Status::RequiresInput => {
-                    comp.send_input(signal);
-
-                    // Here we rely on the fact that coroutines always
-                    // produce output for every input and then either
-                    // require more input or halt, as per
-                    // specification.
-                    signal = comp.peek_output().unwrap();
-                }
-                Status::ProducedOutput(_) => panic!("we don't expect output here"),
-                Status::Halted => {
-                    halted[ix] = true;
-                }
+        for (amplifier, queue) in amplifiers.iter_mut().zip(queues.iter()) {
+            queue.borrow_mut().push_back(signal);
+            match amplifier.next() {
+                Some(Ok(out)) => signal = out,
+                Some(Err(fault)) => panic!("amplifier faulted: {}", fault),
+                None => return signal,
             }
         }
-
-        if halted.iter().all(|x| *x) {
-            break;
-        }
     }
-
-    signal
 }
 
 #[cfg(test)]
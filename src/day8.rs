@@ -1,24 +1,34 @@
-use std::fs;
-
-pub fn solve_part1() -> usize {
-    let input = fs::read_to_string("input/day8.txt").unwrap();
-    let min_ix = input
-        .trim()
-        .as_bytes()
-        .chunks(25 * 6)
-        .enumerate()
-        .min_by_key(|(_ix, layer)| count_char(layer, '0'))
-        .unwrap()
-        .0;
-
-    let min_layer = input.as_bytes().chunks(25 * 6).nth(min_ix).unwrap();
-    count_char(min_layer, '1') * count_char(min_layer, '2')
-}
+use crate::answer::{Answer, Bitmap};
+use crate::ocr;
+use crate::solvers::Solution;
+
+pub struct Day8;
+
+impl Solution for Day8 {
+    type Input = Vec<u8>;
+    type Part1 = usize;
+    type Part2 = Answer;
 
-pub fn solve_part2() {
-    let input = fs::read_to_string("input/day8.txt").unwrap();
-    let bytes = input.trim().as_bytes();
+    fn parse(input: &str) -> Vec<u8> {
+        input.trim().as_bytes().to_vec()
+    }
+
+    fn part1(bytes: &Vec<u8>) -> usize {
+        let min_layer = bytes
+            .chunks(25 * 6)
+            .min_by_key(|layer| count_char(layer, '0'))
+            .unwrap();
+        count_char(min_layer, '1') * count_char(min_layer, '2')
+    }
 
+    fn part2(bytes: &Vec<u8>) -> Answer {
+        ocr::answer(decode(bytes))
+    }
+}
+
+/// Stacks the layers, white pixels are lit and transparent ones which
+/// stay transparent are left dark.
+fn decode(bytes: &[u8]) -> Bitmap {
     let mut image = vec![2; 25 * 6];
 
     // Handle layering.
@@ -33,21 +43,13 @@ pub fn solve_part2() {
         }
     }
 
-    // Display resulting image.
+    let mut bitmap = Bitmap::new(25, 6);
     for y in 0..6 {
         for x in 0..25 {
-            let coord_1d = y * 25 + x;
-            print!(
-                "{}",
-                match image[coord_1d] {
-                    1 => "*",
-                    0 => " ",
-                    _ => "!",
-                }
-            );
+            bitmap.set(x, y, image[y * 25 + x] == 1);
         }
-        println!();
     }
+    bitmap
 }
 
 fn count_char(layer: &[u8], c: char) -> usize {
@@ -1,17 +1,30 @@
 use crate::intcode::*;
+use crate::solvers::Solution;
 
-pub fn solve_part1() -> i64 {
-    let mut comp = Computer::new();
-    comp.load_from_file("input/day9.txt");
-    comp.set_input_lines(&["1"]);
-    comp.run();
-    comp.last_output().parse().unwrap()
+pub struct Day9;
+
+impl Solution for Day9 {
+    type Input = Vec<i64>;
+    type Part1 = i64;
+    type Part2 = i64;
+
+    fn parse(input: &str) -> Vec<i64> {
+        Computer::parse_program(input)
+    }
+
+    fn part1(program: &Vec<i64>) -> i64 {
+        run_with_input(program, "1")
+    }
+
+    fn part2(program: &Vec<i64>) -> i64 {
+        run_with_input(program, "2")
+    }
 }
 
-pub fn solve_part2() -> i64 {
+fn run_with_input(program: &[i64], input: &str) -> i64 {
     let mut comp = Computer::new();
-    comp.load_from_file("input/day9.txt");
-    comp.set_input_lines(&["2"]);
+    comp.load_memory(program);
+    comp.set_input_lines(&[input]);
     comp.run();
     comp.last_output().parse().unwrap()
 }
@@ -0,0 +1,192 @@
+//! Confirmed answers of puzzle parts for every set of inputs, kept in a
+//! text file:
+//!
+//! ```text
+//! aoc-answers v1
+//! # day part input answer
+//! 1 1 default 3296269
+//! 8 2 default EJRGP
+//! ```
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::answer::Answer;
+
+const HEADER: &str = "aoc-answers v1";
+
+/// Day, part and name of the input set.
+pub type Key = (u32, u32, String);
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Expected {
+    answers: BTreeMap<Key, String>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Outcome {
+    Pass,
+    Fail {
+        expected: String,
+    },
+    /// There's no confirmed answer to compare with.
+    Missing,
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Pass => write!(f, "pass"),
+            Outcome::Fail { expected } => write!(f, "FAIL, expected {}", expected),
+            Outcome::Missing => write!(f, "missing"),
+        }
+    }
+}
+
+impl Expected {
+    pub fn new() -> Self {
+        Expected::default()
+    }
+
+    pub fn get(&self, day: u32, part: u32, input: &str) -> Option<&str> {
+        self.answers
+            .get(&(day, part, input.to_owned()))
+            .map(String::as_str)
+    }
+
+    /// Compares `answer` with the confirmed one.
+    pub fn check(&self, day: u32, part: u32, input: &str, answer: &Answer) -> Outcome {
+        match self.get(day, part, input) {
+            None => Outcome::Missing,
+            Some(expected) if expected == answer.to_string() => Outcome::Pass,
+            Some(expected) => Outcome::Fail {
+                expected: expected.to_owned(),
+            },
+        }
+    }
+
+    /// Stores `answer` as the confirmed one, replacing what was there.
+    /// Answers spanning several lines, like unread bitmaps, can't be
+    /// stored.
+    pub fn record(
+        &mut self,
+        day: u32,
+        part: u32,
+        input: &str,
+        answer: &Answer,
+    ) -> Result<(), String> {
+        let text = answer.to_string();
+        if text.is_empty() || text.contains('\n') {
+            return Err(format!(
+                "answer of day {}, part {} isn't a single line",
+                day, part
+            ));
+        }
+        if input.contains(char::is_whitespace) {
+            return Err(format!("input name '{}' has spaces", input));
+        }
+        self.answers.insert((day, part, input.to_owned()), text);
+        Ok(())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &Key> {
+        self.answers.keys()
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_string())
+    }
+
+    /// Loads the answers, a file which doesn't exist has none.
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(Expected::new()),
+            Err(e) => return Err(e),
+        };
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next() != Some(HEADER) {
+            return Err(format!("answers should start with '{}'", HEADER));
+        }
+
+        let mut expected = Expected::new();
+        for line in lines {
+            let fields: Vec<&str> = line.splitn(4, ' ').collect();
+            if fields.len() < 4 {
+                return Err(format!("expected day, part, input and answer: {}", line));
+            }
+            let day = fields[0]
+                .parse()
+                .map_err(|e| format!("bad day in '{}': {}", line, e))?;
+            let part = fields[1]
+                .parse()
+                .map_err(|e| format!("bad part in '{}': {}", line, e))?;
+            let key = (day, part, fields[2].to_owned());
+            if expected.answers.contains_key(&key) {
+                return Err(format!("duplicate answer: {}", line));
+            }
+            expected.answers.insert(key, fields[3].trim().to_owned());
+        }
+        Ok(expected)
+    }
+}
+
+impl fmt::Display for Expected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "# day part input answer")?;
+        for ((day, part, input), answer) in self.answers.iter() {
+            writeln!(f, "{} {} {} {}", day, part, input, answer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_expected() {
+        let mut expected =
+            Expected::parse("aoc-answers v1\n1 1 default 3296269\n8 2 mine EJRGP\n").unwrap();
+        assert_eq!(expected.get(8, 2, "mine"), Some("EJRGP"));
+
+        let answer = Answer::Integer(3296269);
+        assert_eq!(expected.check(1, 1, "default", &answer), Outcome::Pass);
+        assert_eq!(expected.check(1, 1, "mine", &answer), Outcome::Missing);
+        assert_eq!(
+            expected.check(8, 2, "mine", &Answer::Text("EJRGB".to_owned())),
+            Outcome::Fail {
+                expected: "EJRGP".to_owned()
+            }
+        );
+
+        expected
+            .record(1, 2, "default", &Answer::Integer(4941547))
+            .unwrap();
+        assert_eq!(Expected::parse(&expected.to_string()), Ok(expected.clone()));
+        let bitmap = Answer::Text("#\n#".to_owned());
+        assert!(expected.record(8, 2, "other", &bitmap).is_err());
+
+        assert!(Expected::parse("1 1 default 1\n").is_err());
+        assert!(Expected::parse("aoc-answers v1\n1 1 default\n").is_err());
+        assert!(Expected::parse("aoc-answers v1\n1 1 a 1\n1 1 a 2\n").is_err());
+    }
+}
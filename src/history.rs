@@ -0,0 +1,317 @@
+//! History of timings of all days, kept per git revision, machine and
+//! input set so runs can be compared with an earlier baseline.
+//!
+//! ```text
+//! aoc-timings v1
+//! # revision machine profile day part parse_ns solve_ns total_ns
+//! 61e6f50 hal default 1 1 2922 126 3088
+//! ```
+
+use std::fmt;
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::timing::Timing;
+
+const HEADER: &str = "aoc-timings v1";
+
+/// Median times of a day and part at a revision.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Record {
+    pub revision: String,
+    pub machine: String,
+    /// Name of the input set.
+    pub profile: String,
+    pub day: u32,
+    pub part: u32,
+    pub parse: Duration,
+    pub solve: Duration,
+    pub total: Duration,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct History {
+    records: Vec<Record>,
+}
+
+impl History {
+    pub fn new() -> Self {
+        History::default()
+    }
+
+    /// Stores the medians of `timings`, replacing what was recorded for
+    /// the revision on the machine with the same input sets before.
+    pub fn add(&mut self, revision: &str, machine: &str, timings: &[Timing]) -> Result<(), String> {
+        for name in [revision, machine].iter() {
+            if name.is_empty() || name.contains(char::is_whitespace) {
+                return Err(format!("'{}' can't be a revision or machine name", name));
+            }
+        }
+        if let Some(t) = timings
+            .iter()
+            .find(|t| t.profile.contains(char::is_whitespace))
+        {
+            return Err(format!("'{}' can't be an input set name", t.profile));
+        }
+        self.records.retain(|r| {
+            r.revision != revision
+                || r.machine != machine
+                || timings.iter().all(|t| t.profile != r.profile)
+        });
+        self.records.extend(timings.iter().map(|t| Record {
+            revision: revision.to_owned(),
+            machine: machine.to_owned(),
+            profile: t.profile.clone(),
+            day: t.day,
+            part: t.part,
+            parse: t.parse.median,
+            solve: t.solve.median,
+            total: t.total.median,
+        }));
+        Ok(())
+    }
+
+    pub fn records<'a>(
+        &'a self,
+        revision: &'a str,
+        machine: &'a str,
+    ) -> impl Iterator<Item = &'a Record> {
+        self.records
+            .iter()
+            .filter(move |r| r.revision == revision && r.machine == machine)
+    }
+
+    /// Revisions recorded on `machine`, oldest first.
+    pub fn revisions(&self, machine: &str) -> Vec<&str> {
+        let mut revisions: Vec<&str> = vec![];
+        for r in self.records.iter().filter(|r| r.machine == machine) {
+            if !revisions.contains(&r.revision.as_str()) {
+                revisions.push(&r.revision);
+            }
+        }
+        revisions
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_string())
+    }
+
+    /// Loads the history, a file which doesn't exist has none.
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(ref e) if e.kind() == io::ErrorKind::NotFound => return Ok(History::new()),
+            Err(e) => return Err(e),
+        };
+        Self::parse(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next() != Some(HEADER) {
+            return Err(format!("timings should start with '{}'", HEADER));
+        }
+
+        let mut history = History::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() != 8 {
+                return Err(format!(
+                    "expected revision, machine, profile, day, part and 3 times: {}",
+                    line
+                ));
+            }
+            let number = |ix: usize| {
+                fields[ix]
+                    .parse::<u64>()
+                    .map_err(|e| format!("bad number in '{}': {}", line, e))
+            };
+            history.records.push(Record {
+                revision: fields[0].to_owned(),
+                machine: fields[1].to_owned(),
+                profile: fields[2].to_owned(),
+                day: number(3)? as u32,
+                part: number(4)? as u32,
+                parse: Duration::from_nanos(number(5)?),
+                solve: Duration::from_nanos(number(6)?),
+                total: Duration::from_nanos(number(7)?),
+            });
+        }
+        Ok(history)
+    }
+}
+
+impl fmt::Display for History {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(
+            f,
+            "# revision machine profile day part parse_ns solve_ns total_ns"
+        )?;
+        for r in self.records.iter() {
+            writeln!(
+                f,
+                "{} {} {} {} {} {} {} {}",
+                r.revision,
+                r.machine,
+                r.profile,
+                r.day,
+                r.part,
+                r.parse.as_nanos(),
+                r.solve.as_nanos(),
+                r.total.as_nanos()
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Total time of a day and part at the baseline and now.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Change {
+    pub day: u32,
+    pub part: u32,
+    pub baseline: Duration,
+    pub current: Duration,
+    /// Slower than the baseline by more than the threshold.
+    pub slower: bool,
+}
+
+impl Change {
+    /// Change of the time in percent of the baseline.
+    pub fn percent(&self) -> f64 {
+        let baseline = self.baseline.as_secs_f64();
+        if baseline == 0.0 {
+            return 0.0;
+        }
+        (self.current.as_secs_f64() - baseline) / baseline * 100.0
+    }
+}
+
+/// Compares `timings` with the same input set, day and part in
+/// `baseline`, ones which aren't in it are left out.
+pub fn compare<'a, I>(baseline: I, timings: &[Timing], threshold_percent: f64) -> Vec<Change>
+where
+    I: IntoIterator<Item = &'a Record>,
+{
+    let baseline: Vec<&Record> = baseline.into_iter().collect();
+    timings
+        .iter()
+        .filter_map(|t| {
+            let record = baseline
+                .iter()
+                .find(|r| r.profile == t.profile && r.day == t.day && r.part == t.part)?;
+            let mut change = Change {
+                day: t.day,
+                part: t.part,
+                baseline: record.total,
+                current: t.total.median,
+                slower: false,
+            };
+            change.slower = change.percent() > threshold_percent;
+            Some(change)
+        })
+        .collect()
+}
+
+pub fn report(changes: &[Change]) -> String {
+    let mut table = format!(
+        "{:>3} {:>4} {:>12} {:>12} {:>8}\n",
+        "day", "part", "baseline", "now", "change"
+    );
+    for c in changes {
+        writeln!(
+            table,
+            "{:>3} {:>4} {:>12?} {:>12?} {:>+7.1}%{}",
+            c.day,
+            c.part,
+            c.baseline,
+            c.current,
+            c.percent(),
+            if c.slower { "  SLOWER" } else { "" }
+        )
+        .unwrap();
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::timing::Stats;
+
+    fn timing(day: u32, part: u32, micros: u64) -> Timing {
+        profile_timing("default", day, part, micros)
+    }
+
+    fn profile_timing(profile: &str, day: u32, part: u32, micros: u64) -> Timing {
+        let stats = Stats::new(&[Duration::from_micros(micros)]);
+        Timing {
+            profile: profile.to_owned(),
+            day,
+            part,
+            parse: Stats::default(),
+            solve: stats,
+            total: stats,
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let mut history = History::new();
+        history
+            .add("abc", "hal", &[timing(1, 1, 100), timing(1, 2, 200)])
+            .unwrap();
+        history.add("abc", "hal", &[timing(1, 1, 90)]).unwrap();
+        history.add("def", "hal", &[timing(1, 1, 80)]).unwrap();
+        assert!(history.add("abc", "my box", &[]).is_err());
+        assert_eq!(history.records("abc", "hal").count(), 1);
+        assert_eq!(history.revisions("hal"), vec!["abc", "def"]);
+        assert_eq!(History::parse(&history.to_string()), Ok(history.clone()));
+        assert!(History::parse("aoc-timings v1\nabc hal default 1 1 5\n").is_err());
+
+        let now = [timing(1, 1, 100), timing(1, 2, 50)];
+        let changes = compare(history.records("abc", "hal"), &now, 10.0);
+        assert_eq!(changes.len(), 1);
+        assert!(changes[0].slower);
+        assert!((changes[0].percent() - 11.1).abs() < 0.1);
+        assert!(!compare(history.records("abc", "hal"), &now, 20.0)[0].slower);
+        assert!(report(&changes).contains("+11.1%  SLOWER"));
+    }
+
+    #[test]
+    fn test_profiles() {
+        let mut history = History::new();
+        history.add("abc", "hal", &[timing(1, 1, 100)]).unwrap();
+        history
+            .add("abc", "hal", &[profile_timing("alice", 1, 1, 10)])
+            .unwrap();
+        assert_eq!(history.records("abc", "hal").count(), 2);
+        assert!(history
+            .add("abc", "hal", &[profile_timing("a b", 1, 1, 10)])
+            .is_err());
+        assert_eq!(History::parse(&history.to_string()), Ok(history.clone()));
+
+        // Only compared with alice's own baseline, bob has none.
+        let alice = [
+            profile_timing("alice", 1, 1, 11),
+            profile_timing("bob", 1, 1, 500),
+        ];
+        let changes = compare(history.records("abc", "hal"), &alice, 50.0);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].baseline, Duration::from_micros(10));
+        assert!(!changes[0].slower);
+    }
+}
@@ -0,0 +1,129 @@
+//! Named sets of puzzle inputs, one per person since everyone gets their
+//! own inputs. The `default` set is `dayN.txt` in the inputs directory,
+//! other sets are subdirectories of it: `<dir>/<name>/dayN.txt`.
+
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::solvers;
+
+pub const DEFAULT: &str = "default";
+
+/// Environment variable with the name of the set to use when none is
+/// given on the command line.
+pub const ENV_VAR: &str = "AOC_PROFILE";
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct InputSet {
+    pub name: String,
+    pub dir: PathBuf,
+}
+
+impl InputSet {
+    /// The set called `name` in the inputs directory `root`.
+    pub fn new<P>(root: P, name: &str) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let dir = if name == DEFAULT {
+            root.as_ref().to_owned()
+        } else {
+            root.as_ref().join(name)
+        };
+        InputSet {
+            name: name.to_owned(),
+            dir,
+        }
+    }
+
+    /// The set named on the command line, or in the environment, or the
+    /// default one.
+    pub fn selected<P>(root: P, name: Option<&str>) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        match name {
+            Some(name) => InputSet::new(root, name),
+            None => InputSet::new(
+                root,
+                &env::var(ENV_VAR).unwrap_or_else(|_| DEFAULT.to_owned()),
+            ),
+        }
+    }
+
+    /// Whether the set's directory is there, a misspelled name has none.
+    pub fn exists(&self) -> bool {
+        self.dir.is_dir()
+    }
+
+    pub fn path(&self, day: u32) -> PathBuf {
+        solvers::input_path(&self.dir, day)
+    }
+
+    pub fn read(&self, day: u32) -> io::Result<String> {
+        fs::read_to_string(self.path(day))
+    }
+}
+
+/// The default set and every subdirectory of `root` with a `dayN.txt`
+/// in it, sorted by name.
+pub fn all<P>(root: P) -> io::Result<Vec<InputSet>>
+where
+    P: AsRef<Path>,
+{
+    let root = root.as_ref();
+    let mut names = vec![];
+    for entry in fs::read_dir(root)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_dir() {
+            continue;
+        }
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name != DEFAULT && has_inputs(&entry.path())? {
+            names.push(name);
+        }
+    }
+    names.sort();
+
+    let mut sets = vec![InputSet::new(root, DEFAULT)];
+    sets.extend(names.iter().map(|name| InputSet::new(root, name)));
+    Ok(sets)
+}
+
+fn has_inputs(dir: &Path) -> io::Result<bool> {
+    for entry in fs::read_dir(dir)? {
+        let name = entry?.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("day") && name.ends_with(".txt") {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_input_sets() {
+        let default = InputSet::new("input", DEFAULT);
+        assert_eq!(default.path(3), Path::new("input/day3.txt"));
+        let alice = InputSet::new("input", "alice");
+        assert_eq!(alice.path(3), Path::new("input/alice/day3.txt"));
+        assert_eq!(InputSet::selected("input", Some("alice")), alice);
+
+        let root = env::temp_dir().join(format!("aoc-inputs-{}", std::process::id()));
+        fs::create_dir_all(root.join("bob")).unwrap();
+        fs::create_dir_all(root.join("empty")).unwrap();
+        fs::write(root.join("bob/day1.txt"), "12\n").unwrap();
+        let names: Vec<String> = all(&root).unwrap().into_iter().map(|s| s.name).collect();
+        assert_eq!(names, vec!["default", "bob"]);
+        assert_eq!(InputSet::new(&root, "bob").read(1).unwrap(), "12\n");
+        assert!(InputSet::new(&root, "empty").exists());
+        assert!(!InputSet::new(&root, "bbo").exists());
+        fs::remove_dir_all(root).unwrap();
+    }
+}
@@ -1,27 +1,268 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::fmt;
 use std::fs;
 use std::io;
 use std::io::BufRead;
 use std::io::BufReader;
 use std::io::Cursor;
+use std::ops::Range;
 use std::path::Path;
+use std::time::Duration;
+use std::time::Instant;
 
 use log::info;
 
+pub mod bundle;
+pub mod calls;
+pub mod conformance;
+pub mod coverage;
+pub mod devices;
+pub mod diff;
+pub mod disasm;
+pub mod optimize;
+pub mod outputs;
+pub mod patch;
+pub mod session;
+
+use calls::CallGraph;
+use coverage::Coverage;
+use session::Event;
+use session::Session;
+
 pub struct Computer {
     memory: Vec<i64>,
     ip: usize,
     rel_base: i64,
     input: Box<dyn BufRead>,
+    ops: InstructionSet,
+    devices: Vec<Mapping>,
+    exit_code: Option<i64>,
+    session: Option<Session>,
+    coverage: Option<Coverage>,
+    calls: Option<CallGraph>,
+    limits: Limits,
+    usage: Usage,
+    total_usage: Usage,
+    pending_fault: Option<Fault>,
     pub output: Vec<String>,
     pub status: Status,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone)]
 pub enum Status {
     RequiresInput,
     ProducedOutput(i64),
     Halted,
+    Faulted(Fault),
+}
+
+/// Error which stops the computer, it can't be resumed after that.
+/// `BadAddress` is a negative address read, written or jumped to.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Fault {
+    Decode { ip: usize, error: DecodeError },
+    NoInput { ip: usize },
+    IncompleteOutput { values: Vec<i64> },
+    MemoryLimit { ip: usize, addr: usize },
+    InstructionLimit { ip: usize },
+    OutputLimit { ip: usize },
+    InputLimit { ip: usize },
+    BadAddress { ip: usize, addr: i64 },
+}
+
+impl fmt::Display for Fault {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fault::Decode { ip, error } => write!(f, "{} at address {}", error, ip),
+            Fault::NoInput { ip } => write!(f, "no input available at address {}", ip),
+            Fault::IncompleteOutput { values } => {
+                write!(f, "halted in the middle of output group: {:?}", values)
+            }
+            Fault::MemoryLimit { ip, addr } => {
+                write!(
+                    f,
+                    "write to {} exceeds memory limit at address {}",
+                    addr, ip
+                )
+            }
+            Fault::InstructionLimit { ip } => {
+                write!(f, "instruction limit reached at address {}", ip)
+            }
+            Fault::OutputLimit { ip } => write!(f, "output limit reached at address {}", ip),
+            Fault::InputLimit { ip } => write!(f, "input limit reached at address {}", ip),
+            Fault::BadAddress { ip, addr } => {
+                write!(f, "bad address {} used at address {}", addr, ip)
+            }
+        }
+    }
+}
+
+/// Resources a program may use, `None` means unlimited. Each limit
+/// faults the computer with its own `Fault` when exceeded.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Limits {
+    /// Memory size in words, including the program itself.
+    pub memory: Option<usize>,
+    pub instructions: Option<u64>,
+    /// Outputs a single run may produce, counted since the program was
+    /// loaded. It doesn't bound `Computer::output`, which keeps what
+    /// every `run` printed.
+    pub outputs_per_run: Option<usize>,
+    pub input_reads: Option<u64>,
+}
+
+/// Resources used and execution statistics of a run, i.e. since the
+/// program was loaded, or of several runs added up.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Usage {
+    /// High-water mark of memory size in words.
+    pub memory: usize,
+    pub instructions: u64,
+    pub outputs: u64,
+    /// Times the program stopped to wait for input.
+    pub input_waits: u64,
+    pub input_reads: u64,
+    /// Time spent executing instructions, waiting for input isn't
+    /// included.
+    pub time: Duration,
+}
+
+impl Usage {
+    pub fn add(&mut self, other: &Usage) {
+        self.memory = self.memory.max(other.memory);
+        self.instructions += other.instructions;
+        self.outputs += other.outputs;
+        self.input_waits += other.input_waits;
+        self.input_reads += other.input_reads;
+        self.time += other.time;
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} instructions in {:?}, {} words of memory, {} outputs, {} input waits, \
+             {} input reads",
+            self.instructions,
+            self.time,
+            self.memory,
+            self.outputs,
+            self.input_waits,
+            self.input_reads
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum DecodeError {
+    UnknownOpCode(i64),
+    UnknownParameterMode(i64),
+    ImmediateWrite,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::UnknownOpCode(op_code) => write!(f, "unknown op code {}", op_code),
+            DecodeError::UnknownParameterMode(mode) => {
+                write!(f, "unknown parameter mode {}", mode)
+            }
+            DecodeError::ImmediateWrite => write!(f, "write address in immediate mode"),
+        }
+    }
+}
+
+/// How an instruction uses its parameter: either reads a value or
+/// writes a result to the address it resolves to.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Param {
+    Read,
+    Write,
+}
+
+/// What the computer should do after a custom instruction has been
+/// executed.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Effect {
+    Continue,
+    Jump(i64),
+    Output(i64),
+    Halt(i64),
+}
+
+/// An instruction defined by host code, registered under its own op
+/// code in an `InstructionSet`.
+///
+/// `execute` receives one argument per parameter: resolved values for
+/// `Param::Read` parameters and zeroes for `Param::Write` ones. Values
+/// left in the `Write` slots are stored at their addresses afterwards.
+pub trait CustomOp {
+    fn mnemonic(&self) -> &str;
+    fn params(&self) -> &[Param];
+    fn execute(&mut self, args: &mut [i64]) -> Effect;
+
+    fn arity(&self) -> usize {
+        self.params().len()
+    }
+}
+
+/// Host code backing a range of memory addresses, see
+/// `Computer::map_device`. Offsets are relative to the start of the
+/// range.
+pub trait Device {
+    fn read(&mut self, offset: usize) -> i64;
+    fn write(&mut self, offset: usize, value: i64);
+}
+
+struct Mapping {
+    addrs: Range<usize>,
+    device: RefCell<Box<dyn Device>>,
+}
+
+/// Custom instructions known to the decoder on top of the built-in
+/// ones.
+#[derive(Default)]
+pub struct InstructionSet {
+    custom: HashMap<i64, Box<dyn CustomOp>>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        InstructionSet {
+            custom: HashMap::new(),
+        }
+    }
+
+    pub fn register<T>(&mut self, op_code: i64, op: T)
+    where
+        T: CustomOp + 'static,
+    {
+        assert!(
+            op_code > 0 && op_code < 100,
+            "op code should be in range 1..99: {}",
+            op_code
+        );
+        assert!(
+            !is_builtin(op_code),
+            "op code {} is taken by a built-in instruction",
+            op_code
+        );
+        self.custom.insert(op_code, Box::new(op));
+    }
+
+    pub fn get(&self, op_code: i64) -> Option<&dyn CustomOp> {
+        self.custom.get(&op_code).map(|op| op.as_ref())
+    }
+
+    fn get_mut(&mut self, op_code: i64) -> &mut dyn CustomOp {
+        self.custom
+            .get_mut(&op_code)
+            .expect("custom op code is not registered")
+            .as_mut()
+    }
 }
 
 impl Computer {
@@ -31,6 +272,16 @@ impl Computer {
             ip: 0,
             rel_base: 0,
             input: Box::new(BufReader::new(io::stdin())),
+            ops: InstructionSet::new(),
+            devices: vec![],
+            exit_code: None,
+            session: None,
+            coverage: None,
+            calls: None,
+            limits: Limits::default(),
+            usage: Usage::default(),
+            total_usage: Usage::default(),
+            pending_fault: None,
             output: vec![],
             status: Status::Halted,
         }
@@ -45,6 +296,65 @@ impl Computer {
         self.set_input(Box::new(BufReader::new(Cursor::new(vec))));
     }
 
+    pub fn register_op<T>(&mut self, op_code: i64, op: T)
+    where
+        T: CustomOp + 'static,
+    {
+        self.ops.register(op_code, op);
+    }
+
+    /// Maps `device` to `addrs`, reads and writes of parameters which
+    /// resolve there go to the device instead of memory. Instructions are
+    /// always fetched from memory. Mappings are kept across program
+    /// reloads.
+    pub fn map_device<T>(&mut self, addrs: Range<usize>, device: T)
+    where
+        T: Device + 'static,
+    {
+        assert!(
+            !self
+                .devices
+                .iter()
+                .any(|m| m.addrs.start < addrs.end && addrs.start < m.addrs.end),
+            "device range {:?} overlaps with another device",
+            addrs
+        );
+        self.devices.push(Mapping {
+            addrs,
+            device: RefCell::new(Box::new(device)),
+        });
+    }
+
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// Statistics of the current run, memory only grows during a run, so
+    /// its current size is the high-water mark.
+    pub fn usage(&self) -> Usage {
+        Usage {
+            memory: self.memory.len(),
+            ..self.usage.clone()
+        }
+    }
+
+    /// Statistics of all runs since the computer was created.
+    pub fn total_usage(&self) -> Usage {
+        let mut total = self.total_usage.clone();
+        total.add(&self.usage());
+        total
+    }
+
+    pub fn instruction_set(&self) -> &InstructionSet {
+        &self.ops
+    }
+
+    /// Code passed to a halting custom instruction, if the program was
+    /// stopped by one.
+    pub fn exit_code(&self) -> Option<i64> {
+        self.exit_code
+    }
+
     pub fn load_from_file<P>(&mut self, path: P)
     where
         P: AsRef<Path>,
@@ -52,17 +362,56 @@ impl Computer {
         self.load_memory(Self::read_program(path));
     }
 
+    /// Starts recording inputs and outputs, should be called right after
+    /// the program is loaded, since the session is tied to the hash of
+    /// the current memory.
+    pub fn start_recording(&mut self) {
+        self.session = Some(Session::new(program_hash(&self.memory)));
+    }
+
+    pub fn stop_recording(&mut self) -> Option<Session> {
+        self.session.take()
+    }
+
+    /// Starts collecting coverage, it's kept across program reloads, so
+    /// several runs can be accumulated into one report.
+    pub fn start_coverage(&mut self) {
+        self.coverage = Some(Coverage::new());
+    }
+
+    pub fn take_coverage(&mut self) -> Option<Coverage> {
+        self.coverage.take()
+    }
+
+    /// Starts tracking calls and returns, counts are kept across program
+    /// reloads but the call stack is not.
+    pub fn start_call_graph(&mut self) {
+        self.calls = Some(CallGraph::new());
+    }
+
+    pub fn take_call_graph(&mut self) -> Option<CallGraph> {
+        self.calls.take()
+    }
+
     pub fn read_program<P>(path: P) -> Vec<i64>
     where
         P: AsRef<Path>,
     {
         let program = fs::read_to_string(path).expect("can't load program");
+        Self::parse_program(&program)
+    }
+
+    pub fn parse_program(program: &str) -> Vec<i64> {
+        Self::try_parse_program(program).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    pub fn try_parse_program(program: &str) -> Result<Vec<i64>, String> {
         program
             .trim()
             .split(",")
             .map(|x| {
                 x.parse()
-                    .expect(format!("can't parse int code in a program: {}", x).as_str())
+                    .map_err(|_| format!("can't parse int code in a program: {}", x))
             })
             .collect()
     }
@@ -71,18 +420,24 @@ impl Computer {
     where
         T: AsRef<[i64]>,
     {
+        let finished = self.usage();
+        self.total_usage.add(&finished);
+        self.usage = Usage::default();
         mem.as_ref().clone_into(&mut self.memory);
         self.ip = 0;
         self.rel_base = 0;
+        self.exit_code = None;
+        self.pending_fault = None;
+        if let Some(ref mut calls) = self.calls {
+            calls.reset_stack();
+        }
     }
 
     pub fn run_with_memory<T>(&mut self, mem: T) -> &[i64]
     where
         T: AsRef<[i64]>,
     {
-        mem.as_ref().clone_into(&mut self.memory);
-        self.ip = 0;
-        self.rel_base = 0;
+        self.load_memory(mem);
         self.run();
         self.dump_memory()
     }
@@ -97,9 +452,17 @@ impl Computer {
 
     pub fn send_input(&mut self, input: i64) {
         assert_eq!(self.status, Status::RequiresInput);
-        if let Instruction::Input(mode) = Instruction::from(self.read_mem(self.ip)) {
+        if Some(self.usage.input_reads) == self.limits.input_reads {
+            self.status = Status::Faulted(Fault::InputLimit { ip: self.ip });
+            return;
+        }
+        self.usage.input_reads += 1;
+        if let Some(ref mut session) = self.session {
+            session.events.push(Event::Input(input));
+        }
+        if let Ok(Instruction::Input(mode)) = self.decode() {
             let write_addr = self.resolve_write_addr(mode, self.read_mem(self.ip + 1));
-            self.write_mem(write_addr as usize, input);
+            self.write_mem(write_addr, input);
             self.ip += 2;
             self.run_as_coroutine();
         } else {
@@ -107,6 +470,13 @@ impl Computer {
         }
     }
 
+    /// Tells a computer waiting for input that there won't be any, it
+    /// stops with `Fault::NoInput`.
+    pub fn end_input(&mut self) {
+        assert_eq!(self.status, Status::RequiresInput);
+        self.status = Status::Faulted(Fault::NoInput { ip: self.ip });
+    }
+
     pub fn peek_output(&self) -> Option<i64> {
         match self.status {
             Status::ProducedOutput(out) => Some(out),
@@ -118,12 +488,15 @@ impl Computer {
         self.run_as_coroutine();
         loop {
             match self.status {
-                Status::Halted => {
+                Status::Halted | Status::Faulted(_) => {
                     break;
                 }
                 Status::RequiresInput => {
                     let mut buf = String::new();
-                    self.input.read_line(&mut buf).expect("cannot read line");
+                    if self.input.read_line(&mut buf).expect("cannot read line") == 0 {
+                        self.status = Status::Faulted(Fault::NoInput { ip: self.ip });
+                        break;
+                    }
                     let input = buf.trim().parse().expect("cannot parse input");
                     self.send_input(input);
                 }
@@ -133,17 +506,37 @@ impl Computer {
                 }
             }
         }
+        info!("resource usage: {}", self.usage());
     }
 
     pub fn run_as_coroutine(&mut self) {
+        let start = Instant::now();
         loop {
-            match Instruction::from(self.read_mem(self.ip)) {
+            if let Some(fault) = self.check_limits() {
+                self.status = Status::Faulted(fault);
+                break;
+            }
+
+            let instruction = match self.decode() {
+                Ok(instruction) => instruction,
+                Err(error) => {
+                    self.status = Status::Faulted(Fault::Decode { ip: self.ip, error });
+                    break;
+                }
+            };
+            self.usage.instructions += 1;
+
+            if let Some(ref mut coverage) = self.coverage {
+                coverage.hit(self.ip);
+            }
+
+            match instruction {
                 Instruction::Add(p1_mode, p2_mode, p3_mode) => {
                     info!("add");
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
                     let param_3 = self.resolve_write_addr(p3_mode, self.read_mem(self.ip + 3));
-                    self.write_mem(param_3 as usize, param_1 + param_2);
+                    self.write_mem(param_3, param_1 + param_2);
                     self.ip += 4;
                 }
 
@@ -152,12 +545,13 @@ impl Computer {
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
                     let param_3 = self.resolve_write_addr(p3_mode, self.read_mem(self.ip + 3));
-                    self.write_mem(param_3 as usize, param_1 * param_2);
+                    self.write_mem(param_3, param_1 * param_2);
                     self.ip += 4;
                 }
 
                 Instruction::Input(_) => {
                     info!("input");
+                    self.usage.input_waits += 1;
                     self.status = Status::RequiresInput;
                     break;
                 }
@@ -165,7 +559,16 @@ impl Computer {
                 Instruction::Output(p1_mode) => {
                     info!("output");
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
+                    if let Some(fault) = self.pending_fault.take() {
+                        self.status = Status::Faulted(fault);
+                        break;
+                    }
+                    if self.output_limit_reached() {
+                        self.status = Status::Faulted(Fault::OutputLimit { ip: self.ip });
+                        break;
+                    }
                     self.ip += 2;
+                    self.usage.outputs += 1;
                     self.status = Status::ProducedOutput(param_1);
                     break;
                 }
@@ -174,8 +577,27 @@ impl Computer {
                     info!("jump if true");
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
-                    if param_1 != 0 {
-                        self.ip = usize::try_from(param_2).expect("ip is not usize");
+                    let taken = param_1 != 0;
+                    if let Some(ref mut coverage) = self.coverage {
+                        coverage.branch(self.ip, taken);
+                    }
+                    if taken {
+                        let target = match usize::try_from(param_2) {
+                            Ok(target) => target,
+                            Err(_) => {
+                                self.status = Status::Faulted(Fault::BadAddress {
+                                    ip: self.ip,
+                                    addr: param_2,
+                                });
+                                break;
+                            }
+                        };
+                        let through_rb =
+                            p2_mode == ParameterMode::Relative && self.read_mem(self.ip + 2) == 0;
+                        if let Some(ref mut calls) = self.calls {
+                            calls.jump(self.ip, 3, target, through_rb, self.rel_base);
+                        }
+                        self.ip = target;
                     } else {
                         self.ip += 3;
                     }
@@ -185,8 +607,27 @@ impl Computer {
                     info!("jump if false");
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
-                    if param_1 == 0 {
-                        self.ip = usize::try_from(param_2).expect("ip is not usize");
+                    let taken = param_1 == 0;
+                    if let Some(ref mut coverage) = self.coverage {
+                        coverage.branch(self.ip, taken);
+                    }
+                    if taken {
+                        let target = match usize::try_from(param_2) {
+                            Ok(target) => target,
+                            Err(_) => {
+                                self.status = Status::Faulted(Fault::BadAddress {
+                                    ip: self.ip,
+                                    addr: param_2,
+                                });
+                                break;
+                            }
+                        };
+                        let through_rb =
+                            p2_mode == ParameterMode::Relative && self.read_mem(self.ip + 2) == 0;
+                        if let Some(ref mut calls) = self.calls {
+                            calls.jump(self.ip, 3, target, through_rb, self.rel_base);
+                        }
+                        self.ip = target;
                     } else {
                         self.ip += 3;
                     }
@@ -197,7 +638,7 @@ impl Computer {
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
                     let param_3 = self.resolve_write_addr(p3_mode, self.read_mem(self.ip + 3));
-                    self.write_mem(param_3 as usize, (param_1 < param_2) as i64);
+                    self.write_mem(param_3, (param_1 < param_2) as i64);
                     self.ip += 4;
                 }
 
@@ -206,7 +647,7 @@ impl Computer {
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     let param_2 = self.resolve_param(p2_mode, self.read_mem(self.ip + 2));
                     let param_3 = self.resolve_write_addr(p3_mode, self.read_mem(self.ip + 3));
-                    self.write_mem(param_3 as usize, (param_1 == param_2) as i64);
+                    self.write_mem(param_3, (param_1 == param_2) as i64);
                     self.ip += 4;
                 }
 
@@ -214,6 +655,9 @@ impl Computer {
                     info!("adjust relative");
                     let param_1 = self.resolve_param(p1_mode, self.read_mem(self.ip + 1));
                     self.rel_base += param_1;
+                    if let Some(ref mut calls) = self.calls {
+                        calls.adjust_rel_base(self.rel_base);
+                    }
                     self.ip += 2;
                 }
 
@@ -222,16 +666,121 @@ impl Computer {
                     self.status = Status::Halted;
                     break;
                 }
+
+                Instruction::Custom(op_code, modes) => {
+                    info!("custom op {}", op_code);
+                    let op_ip = self.ip;
+                    let params = self.ops.get(op_code).unwrap().params().to_vec();
+                    let mut args = vec![0; params.len()];
+                    let mut write_addrs = vec![];
+                    for (ix, (param, mode)) in params.iter().zip(modes).enumerate() {
+                        let raw = self.read_mem(self.ip + 1 + ix);
+                        match param {
+                            Param::Read => args[ix] = self.resolve_param(mode, raw),
+                            Param::Write => {
+                                write_addrs.push((ix, self.resolve_write_addr(mode, raw)))
+                            }
+                        }
+                    }
+
+                    let effect = self.ops.get_mut(op_code).execute(&mut args);
+                    for (ix, addr) in write_addrs {
+                        self.write_mem(addr, args[ix]);
+                    }
+                    self.ip += 1 + params.len();
+
+                    match effect {
+                        Effect::Continue => {}
+                        Effect::Jump(addr) => match usize::try_from(addr) {
+                            Ok(addr) => self.ip = addr,
+                            Err(_) => {
+                                self.status =
+                                    Status::Faulted(Fault::BadAddress { ip: op_ip, addr });
+                                break;
+                            }
+                        },
+                        Effect::Output(_) if self.output_limit_reached() => {
+                            self.status = Status::Faulted(Fault::OutputLimit { ip: op_ip });
+                            break;
+                        }
+                        Effect::Output(out) => {
+                            self.usage.outputs += 1;
+                            self.status = Status::ProducedOutput(out);
+                            break;
+                        }
+                        Effect::Halt(code) => {
+                            self.exit_code = Some(code);
+                            self.status = Status::Halted;
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+
+        self.usage.time += start.elapsed();
+        self.record_status();
+    }
+
+    fn check_limits(&mut self) -> Option<Fault> {
+        if let Some(fault) = self.pending_fault.take() {
+            return Some(fault);
+        }
+        if Some(self.usage.instructions) == self.limits.instructions {
+            return Some(Fault::InstructionLimit { ip: self.ip });
+        }
+        match self.limits.memory {
+            Some(max) if self.memory.len() > max => Some(Fault::MemoryLimit {
+                ip: self.ip,
+                addr: self.memory.len() - 1,
+            }),
+            _ => None,
+        }
+    }
+
+    fn output_limit_reached(&self) -> bool {
+        Some(self.usage.outputs as usize) == self.limits.outputs_per_run
+    }
+
+    fn record_status(&mut self) {
+        if let Some(ref mut session) = self.session {
+            let event = match self.status {
+                Status::ProducedOutput(out) => Event::Output(out),
+                Status::Halted => Event::Halt,
+                _ => return,
+            };
+            if session.events.last() != Some(&Event::Halt) {
+                session.events.push(event);
             }
         }
     }
 
-    fn write_mem(&mut self, addr: usize, value: i64) {
+    fn decode(&self) -> Result<Instruction, DecodeError> {
+        Instruction::decode(self.read_mem(self.ip), &self.ops)
+    }
+
+    fn write_mem(&mut self, addr: i64, value: i64) {
+        let addr = match self.address(addr) {
+            Some(addr) => addr,
+            None => return,
+        };
+        if let Some(m) = self.device_at(addr) {
+            info!("writing to device at addr: {}, value: {}", addr, value);
+            m.device.borrow_mut().write(addr - m.addrs.start, value);
+            return;
+        }
         if addr >= self.memory.len() {
+            if matches!(self.limits.memory, Some(max) if addr >= max) {
+                self.pending_fault = Some(Fault::MemoryLimit { ip: self.ip, addr });
+                return;
+            }
             self.memory.resize(addr + 1, 0);
         }
         info!("writing to addr: {}, value: {}", addr, value);
         self.memory[addr] = value;
+        if let Some(ref mut calls) = self.calls {
+            calls.write(addr, value, self.rel_base);
+        }
     }
 
     fn read_mem(&self, addr: usize) -> i64 {
@@ -242,44 +791,96 @@ impl Computer {
         }
     }
 
-    fn resolve_param(&self, mode: ParameterMode, param: i64) -> i64 {
-        info!("resolving param {} with mode: {:?}", param, mode);
-        match mode {
-            ParameterMode::Immediate => param,
-            ParameterMode::Position => self.read_mem(param as usize),
-            ParameterMode::Relative => self.read_mem((param + self.rel_base) as usize),
+    /// Reads a parameter value, going through a device if one is mapped
+    /// at `addr`.
+    fn load(&self, addr: usize) -> i64 {
+        match self.device_at(addr) {
+            Some(m) => m.device.borrow_mut().read(addr - m.addrs.start),
+            None => self.read_mem(addr),
         }
     }
 
-    fn resolve_write_addr(&self, mode: ParameterMode, param: i64) -> usize {
+    fn device_at(&self, addr: usize) -> Option<&Mapping> {
+        self.devices.iter().find(|m| m.addrs.contains(&addr))
+    }
+
+    /// Checks an address used by the current instruction, a negative one
+    /// faults the computer before the next instruction.
+    fn address(&mut self, addr: i64) -> Option<usize> {
+        let checked = usize::try_from(addr).ok();
+        if checked.is_none() && self.pending_fault.is_none() {
+            self.pending_fault = Some(Fault::BadAddress { ip: self.ip, addr });
+        }
+        checked
+    }
+
+    fn resolve_param(&mut self, mode: ParameterMode, param: i64) -> i64 {
+        info!("resolving param {} with mode: {:?}", param, mode);
+        let addr = match mode {
+            ParameterMode::Immediate => return param,
+            ParameterMode::Position => param,
+            ParameterMode::Relative => param + self.rel_base,
+        };
+        self.address(addr).map_or(0, |addr| self.load(addr))
+    }
+
+    fn resolve_write_addr(&self, mode: ParameterMode, param: i64) -> i64 {
         info!("resolving write addr {} with mode: {:?}", param, mode);
         match mode {
             ParameterMode::Immediate => panic!("write address can't use Immediate mode"),
-            ParameterMode::Position => param as usize,
-            ParameterMode::Relative => (param + self.rel_base) as usize,
+            ParameterMode::Position => param,
+            ParameterMode::Relative => param + self.rel_base,
         }
     }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+/// FNV-1a hash of a memory image, stable between runs and platforms.
+pub fn program_hash(mem: &[i64]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+    for word in mem {
+        for byte in word.to_le_bytes().iter() {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    hash
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
 enum ParameterMode {
     Position,
     Immediate,
     Relative,
 }
 
-impl From<i64> for ParameterMode {
-    fn from(data: i64) -> Self {
+impl ParameterMode {
+    fn digit(self) -> i64 {
+        match self {
+            ParameterMode::Position => 0,
+            ParameterMode::Immediate => 1,
+            ParameterMode::Relative => 2,
+        }
+    }
+}
+
+impl TryFrom<i64> for ParameterMode {
+    type Error = DecodeError;
+
+    fn try_from(data: i64) -> Result<Self, Self::Error> {
         match data {
-            0 => ParameterMode::Position,
-            1 => ParameterMode::Immediate,
-            2 => ParameterMode::Relative,
-            x => panic!("Unknown parameter mode: {}", x),
+            0 => Ok(ParameterMode::Position),
+            1 => Ok(ParameterMode::Immediate),
+            2 => Ok(ParameterMode::Relative),
+            x => Err(DecodeError::UnknownParameterMode(x)),
         }
     }
 }
 
-#[derive(PartialEq, Eq)]
+fn is_builtin(op_code: i64) -> bool {
+    (1..=9).contains(&op_code) || op_code == 99
+}
+
+#[derive(PartialEq, Eq, Debug)]
 enum Instruction {
     Add(ParameterMode, ParameterMode, ParameterMode),
     Mul(ParameterMode, ParameterMode, ParameterMode),
@@ -291,49 +892,97 @@ enum Instruction {
     Equals(ParameterMode, ParameterMode, ParameterMode),
     AdjustRelativeBase(ParameterMode),
     Stop,
+    Custom(i64, Vec<ParameterMode>),
 }
 
-impl From<i64> for Instruction {
-    fn from(data: i64) -> Self {
-        assert!(data > 0);
+impl Instruction {
+    fn decode(data: i64, ops: &InstructionSet) -> Result<Self, DecodeError> {
+        if data <= 0 {
+            return Err(DecodeError::UnknownOpCode(data));
+        }
+
         let op_code = data % 100;
-        let param1_mode = ParameterMode::from((data / 100) % 10);
-        let param2_mode = ParameterMode::from((data / 1_000) % 10);
-        let param3_mode = ParameterMode::from((data / 10_000) % 10);
+        let mode = |ix: u32| ParameterMode::try_from((data / 10_i64.pow(ix + 2)) % 10);
+        let write_mode = |ix: u32| match mode(ix)? {
+            ParameterMode::Immediate => Err(DecodeError::ImmediateWrite),
+            m => Ok(m),
+        };
 
-        info!(
-            "param modes: p1: {:?}, p2: {:?}, p3: {:?}",
-            param1_mode, param2_mode, param3_mode
-        );
-        match op_code {
-            1 => {
-                assert!(param3_mode != ParameterMode::Immediate);
-                Instruction::Add(param1_mode, param2_mode, param3_mode)
-            }
-            2 => {
-                assert!(param3_mode != ParameterMode::Immediate);
-                Instruction::Mul(param1_mode, param2_mode, param3_mode)
-            }
-            3 => {
-                assert!(param1_mode != ParameterMode::Immediate);
-                Instruction::Input(param1_mode)
-            }
-            4 => Instruction::Output(param1_mode),
-            5 => Instruction::JumpIfTrue(param1_mode, param2_mode),
-            6 => Instruction::JumpIfFalse(param1_mode, param2_mode),
-            7 => {
-                assert!(param3_mode != ParameterMode::Immediate);
-                Instruction::LessThan(param1_mode, param2_mode, param3_mode)
-            }
-            8 => {
-                assert!(param3_mode != ParameterMode::Immediate);
-                Instruction::Equals(param1_mode, param2_mode, param3_mode)
-            }
-            9 => Instruction::AdjustRelativeBase(param1_mode),
+        let instruction = match op_code {
+            1 => Instruction::Add(mode(0)?, mode(1)?, write_mode(2)?),
+            2 => Instruction::Mul(mode(0)?, mode(1)?, write_mode(2)?),
+            3 => Instruction::Input(write_mode(0)?),
+            4 => Instruction::Output(mode(0)?),
+            5 => Instruction::JumpIfTrue(mode(0)?, mode(1)?),
+            6 => Instruction::JumpIfFalse(mode(0)?, mode(1)?),
+            7 => Instruction::LessThan(mode(0)?, mode(1)?, write_mode(2)?),
+            8 => Instruction::Equals(mode(0)?, mode(1)?, write_mode(2)?),
+            9 => Instruction::AdjustRelativeBase(mode(0)?),
             99 => Instruction::Stop,
-            _ => panic!("Unknown op code: {}", op_code),
+            _ => match ops.get(op_code) {
+                Some(op) => {
+                    let mut modes = vec![];
+                    for (ix, param) in op.params().iter().enumerate() {
+                        modes.push(match param {
+                            Param::Read => mode(ix as u32)?,
+                            Param::Write => write_mode(ix as u32)?,
+                        });
+                    }
+                    Instruction::Custom(op_code, modes)
+                }
+                None => return Err(DecodeError::UnknownOpCode(op_code)),
+            },
+        };
+
+        info!("decoded: {:?}", instruction);
+        Ok(instruction)
+    }
+
+    fn mnemonic<'a>(&self, ops: &'a InstructionSet) -> &'a str {
+        match self {
+            Instruction::Add(..) => "add",
+            Instruction::Mul(..) => "mul",
+            Instruction::Input(..) => "in",
+            Instruction::Output(..) => "out",
+            Instruction::JumpIfTrue(..) => "jt",
+            Instruction::JumpIfFalse(..) => "jf",
+            Instruction::LessThan(..) => "lt",
+            Instruction::Equals(..) => "eq",
+            Instruction::AdjustRelativeBase(..) => "arb",
+            Instruction::Stop => "halt",
+            Instruction::Custom(op_code, _) => ops.get(*op_code).unwrap().mnemonic(),
+        }
+    }
+
+    fn params(&self, ops: &InstructionSet) -> Vec<Param> {
+        use Instruction::*;
+        use Param::*;
+        match self {
+            Add(..) | Mul(..) | LessThan(..) | Equals(..) => vec![Read, Read, Write],
+            JumpIfTrue(..) | JumpIfFalse(..) => vec![Read, Read],
+            Input(..) => vec![Write],
+            Output(..) | AdjustRelativeBase(..) => vec![Read],
+            Stop => vec![],
+            Custom(op_code, _) => ops.get(*op_code).unwrap().params().to_vec(),
+        }
+    }
+
+    fn modes(&self) -> Vec<ParameterMode> {
+        use Instruction::*;
+        match self {
+            Add(p1, p2, p3) | Mul(p1, p2, p3) | LessThan(p1, p2, p3) | Equals(p1, p2, p3) => {
+                vec![*p1, *p2, *p3]
+            }
+            JumpIfTrue(p1, p2) | JumpIfFalse(p1, p2) => vec![*p1, *p2],
+            Input(p1) | Output(p1) | AdjustRelativeBase(p1) => vec![*p1],
+            Stop => vec![],
+            Custom(_, modes) => modes.clone(),
         }
     }
+
+    fn len(&self) -> usize {
+        self.modes().len() + 1
+    }
 }
 
 #[cfg(test)]
@@ -400,4 +1049,190 @@ mod tests {
             ],
         );
     }
+
+    struct HaltWithCode;
+
+    impl CustomOp for HaltWithCode {
+        fn mnemonic(&self) -> &str {
+            "exit"
+        }
+
+        fn params(&self) -> &[Param] {
+            &[Param::Read]
+        }
+
+        fn execute(&mut self, args: &mut [i64]) -> Effect {
+            Effect::Halt(args[0])
+        }
+    }
+
+    struct Square;
+
+    impl CustomOp for Square {
+        fn mnemonic(&self) -> &str {
+            "sqr"
+        }
+
+        fn params(&self) -> &[Param] {
+            &[Param::Read, Param::Write]
+        }
+
+        fn execute(&mut self, args: &mut [i64]) -> Effect {
+            args[1] = args[0] * args[0];
+            Effect::Continue
+        }
+    }
+
+    #[test]
+    fn test_custom_ops() {
+        let mut comp = Computer::new();
+        comp.register_op(20, Square);
+        comp.register_op(21, HaltWithCode);
+
+        test_computer(
+            &mut comp,
+            vec![20, 4, 5, 99, 7, 0],
+            vec![20, 4, 5, 99, 7, 49],
+        );
+        assert_eq!(comp.exit_code(), None);
+
+        comp.run_with_memory(vec![120, 3, 0, 2021, 0, 99]);
+        assert_eq!(comp.status, Status::Halted);
+        assert_eq!(comp.exit_code(), Some(9));
+    }
+
+    #[test]
+    fn test_unknown_op_code() {
+        let mut comp = Computer::new();
+        comp.run_with_memory(vec![1101, 1, 1, 5, 20, 0]);
+        assert_eq!(
+            comp.status,
+            Status::Faulted(Fault::Decode {
+                ip: 4,
+                error: DecodeError::UnknownOpCode(20)
+            })
+        );
+
+        comp.run_with_memory(vec![11101, 1, 1, 5, 99]);
+        assert_eq!(
+            comp.status,
+            Status::Faulted(Fault::Decode {
+                ip: 0,
+                error: DecodeError::ImmediateWrite
+            })
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        // Echoes inputs forever.
+        let echo = vec![3, 100, 4, 100, 1105, 1, 0];
+        let limited = |limits: Limits, input: &[&str]| {
+            let mut comp = Computer::new();
+            comp.set_limits(limits);
+            comp.set_input_lines(input);
+            comp.run_with_memory(&echo);
+            (comp.status.clone(), comp.usage())
+        };
+
+        let (status, usage) = limited(
+            Limits {
+                memory: Some(100),
+                ..Limits::default()
+            },
+            &["1"],
+        );
+        assert_eq!(
+            status,
+            Status::Faulted(Fault::MemoryLimit { ip: 0, addr: 100 })
+        );
+        assert_eq!(usage.memory, 7);
+
+        let (status, usage) = limited(
+            Limits {
+                instructions: Some(5),
+                ..Limits::default()
+            },
+            &["1", "2"],
+        );
+        assert_eq!(status, Status::Faulted(Fault::InstructionLimit { ip: 4 }));
+        assert_eq!(usage.instructions, 5);
+        assert_eq!(usage.outputs, 2);
+
+        let (status, _) = limited(
+            Limits {
+                outputs_per_run: Some(2),
+                ..Limits::default()
+            },
+            &["1", "2", "3"],
+        );
+        assert_eq!(status, Status::Faulted(Fault::OutputLimit { ip: 2 }));
+
+        // Each run gets the whole limit, also without `run`.
+        let mut comp = Computer::new();
+        comp.set_limits(Limits {
+            outputs_per_run: Some(2),
+            ..Limits::default()
+        });
+        for _ in 0..2 {
+            comp.set_input_lines(&["1", "2", "3"]);
+            comp.run_with_memory(&echo);
+            assert_eq!(comp.status, Status::Faulted(Fault::OutputLimit { ip: 2 }));
+            assert_eq!(comp.usage().outputs, 2);
+        }
+        comp.load_memory(&echo);
+        let outputs: Vec<_> = comp.outputs_with_input(|| 7).collect();
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[2], Err(Fault::OutputLimit { ip: 2 }));
+
+        // Negative addresses fault instead of crashing.
+        let mut comp = Computer::new();
+        for program in &[
+            vec![1105, 1, -1],
+            vec![1101, 1, 1, -1, 99],
+            vec![204, -1, 99],
+        ] {
+            comp.run_with_memory(program);
+            assert_eq!(
+                comp.status,
+                Status::Faulted(Fault::BadAddress { ip: 0, addr: -1 })
+            );
+        }
+
+        let (status, usage) = limited(
+            Limits {
+                input_reads: Some(2),
+                ..Limits::default()
+            },
+            &["1", "2", "3"],
+        );
+        assert_eq!(status, Status::Faulted(Fault::InputLimit { ip: 0 }));
+        assert_eq!(usage.input_reads, 2);
+        assert_eq!(usage.memory, 101);
+    }
+
+    #[test]
+    fn test_usage() {
+        let mut comp = Computer::new();
+        comp.load_from_file("input/day9.txt");
+        comp.set_input_lines(&["1"]);
+        comp.run();
+        let boost = comp.usage();
+        assert_eq!(boost.instructions, 207);
+        assert_eq!(boost.memory, 1030);
+        assert_eq!(
+            (boost.input_waits, boost.input_reads, boost.outputs),
+            (1, 1, 1)
+        );
+
+        comp.run_with_memory(vec![1101, 1, 1, 5, 99, 0]);
+        let usage = comp.usage();
+        assert_eq!((usage.instructions, usage.memory, usage.outputs), (2, 6, 0));
+
+        let total = comp.total_usage();
+        assert_eq!(total.instructions, boost.instructions + 2);
+        assert_eq!(total.memory, boost.memory);
+        assert_eq!(total.input_waits, 1);
+        assert!(total.time >= boost.time);
+    }
 }
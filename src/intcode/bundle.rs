@@ -0,0 +1,299 @@
+//! Bundles of named Intcode programs with metadata, and a registry to
+//! look them up by name.
+//!
+//! A bundle is a text file:
+//!
+//! ```text
+//! intcode-bundle v1
+//!
+//! program day2
+//! source Advent of Code 2019, day 2
+//! file day2.txt
+//! patch alarm 1=12, 2=2
+//!
+//! program double
+//! code 3,9,102,2,9,9,4,9,99,0
+//! input 21
+//! output 42
+//! ```
+//!
+//! Every program needs either `file`, a path relative to the bundle, or
+//! inline `code`. `input` and `output` describe an expected run and
+//! `patch` lines define named patches.
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::patch::Patch;
+use super::*;
+
+const HEADER: &str = "intcode-bundle v1";
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Program {
+    pub name: String,
+    pub source: Option<String>,
+    pub code: Vec<i64>,
+    pub input: Vec<i64>,
+    pub output: Option<Vec<i64>>,
+    pub patches: BTreeMap<String, Patch>,
+}
+
+impl Program {
+    /// Code with the named patches applied in order.
+    pub fn patched(&self, patches: &[&str]) -> Result<Vec<i64>, String> {
+        let mut code = self.code.clone();
+        for name in patches {
+            self.patches
+                .get(*name)
+                .ok_or_else(|| format!("program {} has no patch {}", self.name, name))?
+                .apply(&mut code)
+                .map_err(|e| format!("patch {} of program {}: {}", name, self.name, e))?;
+        }
+        Ok(code)
+    }
+
+    /// Runs the program with its `input` and compares what it outputs
+    /// with `output`. Programs without expected output always pass.
+    pub fn check(&self, comp: &mut Computer) -> Result<(), String> {
+        let expected = match self.output {
+            Some(ref output) => output,
+            None => return Ok(()),
+        };
+        comp.load_memory(&self.code);
+        let mut input = self.input.iter();
+        let actual = comp
+            .outputs_with_input(|| *input.next().unwrap_or(&0))
+            .collect::<Result<Vec<i64>, Fault>>()
+            .map_err(|fault| format!("program {} faulted: {}", self.name, fault))?;
+        if &actual != expected {
+            return Err(format!(
+                "program {}: expected output {:?}, got {:?}",
+                self.name, expected, actual
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Bundle {
+    pub programs: Vec<Program>,
+}
+
+impl Bundle {
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let path = path.as_ref();
+        let base = path.parent().unwrap_or_else(|| Path::new("."));
+        Self::parse(&fs::read_to_string(path)?, base)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// Parses a bundle, `file` paths are resolved relative to `base`.
+    pub fn parse(s: &str, base: &Path) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next() != Some(HEADER) {
+            return Err(format!("bundle should start with '{}'", HEADER));
+        }
+
+        let mut programs: Vec<Program> = vec![];
+        for line in lines {
+            let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+            let value = value.trim();
+            if key == "program" {
+                if programs.iter().any(|p| p.name == value) {
+                    return Err(format!("duplicate program {}", value));
+                }
+                programs.push(Program {
+                    name: value.to_owned(),
+                    ..Program::default()
+                });
+                continue;
+            }
+
+            let program = programs
+                .last_mut()
+                .ok_or_else(|| format!("'{}' before the first program", line))?;
+            match key {
+                "source" => program.source = Some(value.to_owned()),
+                "file" => {
+                    let path = base.join(value);
+                    let code = fs::read_to_string(&path)
+                        .map_err(|e| format!("can't read {}: {}", path.display(), e))?;
+                    program.code = parse_values(&code)?;
+                }
+                "code" => program.code = parse_values(value)?,
+                "input" => program.input = parse_values(value)?,
+                "output" => program.output = Some(parse_values(value)?),
+                "patch" => {
+                    let (name, patch) = value
+                        .split_once(' ')
+                        .ok_or_else(|| format!("patch needs a name: {}", line))?;
+                    program.patches.insert(name.to_owned(), patch.parse()?);
+                }
+                _ => return Err(format!("unknown key: {}", line)),
+            }
+        }
+
+        if let Some(p) = programs.iter().find(|p| p.code.is_empty()) {
+            return Err(format!("program {} has no code", p.name));
+        }
+        Ok(Bundle { programs })
+    }
+}
+
+fn parse_values(s: &str) -> Result<Vec<i64>, String> {
+    s.split(',')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(|v| v.parse().map_err(|e| format!("bad value '{}': {}", v, e)))
+        .collect()
+}
+
+fn join(values: &[i64]) -> String {
+    values
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Writes all code inline, so a saved bundle doesn't depend on other
+/// files.
+impl fmt::Display for Bundle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        for p in self.programs.iter() {
+            writeln!(f)?;
+            writeln!(f, "program {}", p.name)?;
+            if let Some(ref source) = p.source {
+                writeln!(f, "source {}", source)?;
+            }
+            writeln!(f, "code {}", join(&p.code))?;
+            if !p.input.is_empty() {
+                writeln!(f, "input {}", join(&p.input))?;
+            }
+            if let Some(ref output) = p.output {
+                writeln!(f, "output {}", join(output))?;
+            }
+            for (name, patch) in p.patches.iter() {
+                writeln!(f, "patch {} {}", name, patch)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Programs from any number of bundles, names should be unique across
+/// all of them.
+#[derive(Debug, Default)]
+pub struct Registry {
+    programs: BTreeMap<String, Program>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        let mut registry = Registry::new();
+        registry
+            .add(Bundle::load(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        Ok(registry)
+    }
+
+    pub fn add(&mut self, bundle: Bundle) -> Result<(), String> {
+        for program in bundle.programs {
+            if self.programs.contains_key(&program.name) {
+                return Err(format!("program {} is already registered", program.name));
+            }
+            self.programs.insert(program.name.clone(), program);
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Program> {
+        self.programs.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.programs.keys().map(String::as_str)
+    }
+
+    /// Code of the program called `name` with the named patches applied.
+    pub fn program(&self, name: &str, patches: &[&str]) -> Result<Vec<i64>, String> {
+        self.get(name)
+            .ok_or_else(|| format!("unknown program {}", name))?
+            .patched(patches)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bundle() {
+        let bundle = Bundle::parse(
+            "intcode-bundle v1\n\
+             program double\n\
+             code 3,9,102,2,9,9,4,9,99,0\n\
+             input 21\n\
+             output 42\n\
+             patch triple 3=3\n",
+            Path::new("."),
+        )
+        .unwrap();
+        let text = bundle.to_string();
+        assert_eq!(Bundle::parse(&text, Path::new(".")).unwrap(), bundle);
+
+        let mut registry = Registry::new();
+        registry.add(bundle.clone()).unwrap();
+        assert!(registry.add(bundle).is_err());
+
+        let double = registry.get("double").unwrap();
+        let mut comp = Computer::new();
+        assert_eq!(double.check(&mut comp), Ok(()));
+        let triple = registry.program("double", &["triple"]).unwrap();
+        comp.load_memory(triple);
+        assert_eq!(
+            comp.outputs_with_input(|| 21).collect::<Vec<_>>(),
+            vec![Ok(63)]
+        );
+        assert!(registry.program("double", &["quadruple"]).is_err());
+    }
+
+    #[test]
+    fn test_inputs_bundle() {
+        let registry = Registry::load("input/intcode.bundle").unwrap();
+        let mut comp = Computer::new();
+        for name in registry.names() {
+            assert_eq!(registry.get(name).unwrap().check(&mut comp), Ok(()));
+        }
+
+        let alarm = registry.program("day2", &["alarm"]).unwrap();
+        assert_eq!(comp.run_with_memory(alarm)[0], 3166704);
+        assert_eq!(registry.program("day13", &["free-play"]).unwrap()[0], 2);
+    }
+}
@@ -0,0 +1,304 @@
+//! Dynamic call graph of programs which use the relative base as a stack
+//! pointer.
+//!
+//! A call is a write of the return address to `[rb+0]` followed by a
+//! taken jump from right before that address. A return is a jump through
+//! `[rb+0]`. The analysis keeps a shadow stack of frames and checks that
+//! every return goes back to the innermost call with the relative base
+//! the call was made with. Other jumps through `[rb+0]`, like the ones in
+//! the day 9 self test, are reported as returns without a matching call.
+
+use std::collections::BTreeMap;
+
+use super::*;
+
+/// Calls seen over one or more runs, collected by `Computer` between
+/// `start_call_graph` and `take_call_graph`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct CallGraph {
+    functions: BTreeMap<usize, Function>,
+    edges: BTreeMap<(usize, usize), u64>,
+    stack: Vec<Frame>,
+    pushed: Option<i64>,
+    max_depth: usize,
+    max_stack: i64,
+    unbalanced: Vec<Unbalanced>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Function {
+    pub calls: u64,
+    /// Deepest nesting the function was called at, top level code is at
+    /// depth 0.
+    pub max_depth: usize,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Frame {
+    pub function: usize,
+    pub call_site: usize,
+    pub return_addr: usize,
+    pub rel_base: i64,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Unbalanced {
+    /// Return to an address which no frame on the stack returns to.
+    UnknownReturn { ip: usize, target: usize },
+    /// Frame dropped by returning straight to one of its callers.
+    Skipped { ip: usize, frame: Frame },
+    /// Return with a different relative base than the call was made with.
+    RelativeBase {
+        ip: usize,
+        frame: Frame,
+        actual: i64,
+    },
+}
+
+impl fmt::Display for Frame {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} called from {} (returns to {}, rb {})",
+            self.function, self.call_site, self.return_addr, self.rel_base
+        )
+    }
+}
+
+impl fmt::Display for Unbalanced {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Unbalanced::UnknownReturn { ip, target } => {
+                write!(f, "{}: return to {} without a matching call", ip, target)
+            }
+            Unbalanced::Skipped { ip, frame } => {
+                write!(f, "{}: return skipped frame of {}", ip, frame)
+            }
+            Unbalanced::RelativeBase { ip, frame, actual } => write!(
+                f,
+                "{}: return from frame of {} with rb {}",
+                ip, frame, actual
+            ),
+        }
+    }
+}
+
+impl CallGraph {
+    pub fn new() -> Self {
+        CallGraph::default()
+    }
+
+    pub fn functions(&self) -> &BTreeMap<usize, Function> {
+        &self.functions
+    }
+
+    /// Number of calls from `caller` to `callee`, top level code is the
+    /// function at address 0.
+    pub fn calls(&self, caller: usize, callee: usize) -> u64 {
+        self.edges.get(&(caller, callee)).cloned().unwrap_or(0)
+    }
+
+    pub fn edges(&self) -> impl Iterator<Item = (usize, usize, u64)> + '_ {
+        self.edges
+            .iter()
+            .map(|(&(caller, callee), &calls)| (caller, callee, calls))
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    /// Largest distance of the relative base from where it was when the
+    /// outermost call was made.
+    pub fn max_stack(&self) -> i64 {
+        self.max_stack
+    }
+
+    pub fn unbalanced(&self) -> &[Unbalanced] {
+        &self.unbalanced
+    }
+
+    /// Frames which weren't returned from yet, outermost first.
+    pub fn open_frames(&self) -> &[Frame] {
+        &self.stack
+    }
+
+    /// Forgets the call stack, e.g. when a new program is loaded.
+    /// Collected counts are kept.
+    pub fn reset_stack(&mut self) {
+        self.stack.clear();
+        self.pushed = None;
+    }
+
+    pub fn write(&mut self, addr: usize, value: i64, rel_base: i64) {
+        if addr as i64 == rel_base {
+            self.pushed = Some(value);
+        }
+    }
+
+    pub fn adjust_rel_base(&mut self, rel_base: i64) {
+        if let Some(bottom) = self.stack.first() {
+            self.max_stack = self.max_stack.max(rel_base - bottom.rel_base);
+        }
+    }
+
+    /// Taken jump from the instruction at `ip` with length `len`.
+    /// `through_rb` tells whether the target was read from `[rb+0]`.
+    pub fn jump(&mut self, ip: usize, len: usize, target: usize, through_rb: bool, rel_base: i64) {
+        let pushed = self.pushed.take();
+        if through_rb {
+            self.ret(ip, target, rel_base);
+        } else if pushed == Some((ip + len) as i64) {
+            self.call(ip, ip + len, target, rel_base);
+        }
+    }
+
+    fn call(&mut self, ip: usize, return_addr: usize, target: usize, rel_base: i64) {
+        let caller = self.stack.last().map_or(0, |f| f.function);
+        *self.edges.entry((caller, target)).or_default() += 1;
+        self.stack.push(Frame {
+            function: target,
+            call_site: ip,
+            return_addr,
+            rel_base,
+        });
+        let depth = self.stack.len();
+        let function = self.functions.entry(target).or_default();
+        function.calls += 1;
+        function.max_depth = function.max_depth.max(depth);
+        self.max_depth = self.max_depth.max(depth);
+    }
+
+    fn ret(&mut self, ip: usize, target: usize, rel_base: i64) {
+        let index = match self.stack.iter().rposition(|f| f.return_addr == target) {
+            Some(index) => index,
+            None => {
+                self.unbalanced
+                    .push(Unbalanced::UnknownReturn { ip, target });
+                return;
+            }
+        };
+        while self.stack.len() > index + 1 {
+            let frame = self.stack.pop().unwrap();
+            self.unbalanced.push(Unbalanced::Skipped { ip, frame });
+        }
+        let frame = self.stack.pop().unwrap();
+        if frame.rel_base != rel_base {
+            self.unbalanced.push(Unbalanced::RelativeBase {
+                ip,
+                frame,
+                actual: rel_base,
+            });
+        }
+    }
+}
+
+impl fmt::Display for CallGraph {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>8} {:>10} {:>6}  callers",
+            "function", "calls", "depth"
+        )?;
+        for (&addr, function) in self.functions.iter() {
+            let callers: Vec<String> = self
+                .edges()
+                .filter(|&(_, callee, _)| callee == addr)
+                .map(|(caller, _, calls)| format!("{} ({})", caller, calls))
+                .collect();
+            writeln!(
+                f,
+                "{:>8} {:>10} {:>6}  {}",
+                addr,
+                function.calls,
+                function.max_depth,
+                callers.join(", ")
+            )?;
+        }
+        writeln!(f, "max call depth: {}", self.max_depth)?;
+        writeln!(f, "max stack size: {}", self.max_stack)?;
+        for unbalanced in self.unbalanced.iter() {
+            writeln!(f, "unbalanced: {}", unbalanced)?;
+        }
+        for frame in self.stack.iter() {
+            writeln!(f, "open frame: {}", frame)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(program: &[i64]) -> CallGraph {
+        let mut comp = Computer::new();
+        comp.start_call_graph();
+        comp.run_with_memory(program);
+        comp.take_call_graph().unwrap()
+    }
+
+    #[test]
+    fn test_call_and_return() {
+        #[rustfmt::skip]
+        let graph = run(&[
+            109, 100,           // arb #100
+            21101, 9, 0, 0,     // add #9, #0, [rb+0]
+            1105, 1, 12,        // jt #1, #12
+            99, 0, 0,           // halt
+            109, 1,             // 12: arb #1
+            104, 42,            // out #42
+            109, -1,            // arb #-1
+            2105, 1, 0,         // jt #1, [rb+0]
+        ]);
+        assert_eq!(graph.calls(0, 12), 1);
+        assert_eq!(graph.functions()[&12].max_depth, 1);
+        assert_eq!(graph.max_depth(), 1);
+        assert_eq!(graph.max_stack(), 1);
+        assert!(graph.unbalanced().is_empty());
+        assert!(graph.open_frames().is_empty());
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        #[rustfmt::skip]
+        let graph = run(&[
+            109, 100,           // arb #100
+            21101, 9, 0, 0,     // add #9, #0, [rb+0]
+            1105, 1, 12,        // jt #1, #12
+            99, 0, 0,           // halt
+            109, 1,             // 12: arb #1
+            21101, 9, 0, 0,     // add #9, #0, [rb+0]
+            2105, 1, 0,         // jt #1, [rb+0]
+        ]);
+        let frame = Frame {
+            function: 12,
+            call_site: 6,
+            return_addr: 9,
+            rel_base: 100,
+        };
+        assert_eq!(
+            graph.unbalanced(),
+            &[Unbalanced::RelativeBase {
+                ip: 18,
+                frame,
+                actual: 101
+            }]
+        );
+    }
+
+    #[test]
+    fn test_recursion() {
+        let mut comp = Computer::new();
+        comp.load_from_file("input/day9.txt");
+        comp.start_call_graph();
+        comp.set_input_lines(&["2"]);
+        comp.run();
+        let graph = comp.take_call_graph().unwrap();
+        assert_eq!(graph.calls(0, 922), 1);
+        assert_eq!(graph.functions()[&922].calls, 37119);
+        assert_eq!(graph.max_depth(), 26);
+        assert!(graph.unbalanced().is_empty());
+        assert!(graph.open_frames().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+//! Golden-file tests for the Intcode computer.
+//!
+//! Every case is a directory with `program.txt` and optional
+//! `input.txt`, `output.txt` and `memory.txt`, all of them
+//! comma-separated lists of integers. Missing `output.txt` or
+//! `memory.txt` means the corresponding check is skipped.
+
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::*;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Case {
+    pub name: String,
+    pub program: Vec<i64>,
+    pub input: Vec<i64>,
+    pub output: Option<Vec<i64>>,
+    pub memory: Option<Vec<i64>>,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub passed: Vec<String>,
+    pub failed: Vec<(String, String)>,
+}
+
+impl Report {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, diff) in &self.failed {
+            writeln!(f, "FAILED {}:\n{}", name, diff)?;
+        }
+        write!(
+            f,
+            "{} passed, {} failed",
+            self.passed.len(),
+            self.failed.len()
+        )
+    }
+}
+
+/// Loads all cases from subdirectories of `dir`, sorted by name.
+pub fn discover<P>(dir: P) -> io::Result<Vec<Case>>
+where
+    P: AsRef<Path>,
+{
+    let mut cases = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            cases.push(load_case(&path)?);
+        }
+    }
+    cases.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(cases)
+}
+
+fn load_case(dir: &Path) -> io::Result<Case> {
+    let read = |file: &str| -> io::Result<Option<Vec<i64>>> {
+        let path = dir.join(file);
+        if !path.exists() {
+            return Ok(None);
+        }
+        parse_ints(&fs::read_to_string(&path)?)
+            .map(Some)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{:?}: {}", path, e)))
+    };
+
+    let name = dir.file_name().unwrap().to_string_lossy().into_owned();
+    let program = read("program.txt")?.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("case {} has no program.txt", name),
+        )
+    })?;
+
+    Ok(Case {
+        name,
+        program,
+        input: read("input.txt")?.unwrap_or_default(),
+        output: read("output.txt")?,
+        memory: read("memory.txt")?,
+    })
+}
+
+fn parse_ints(s: &str) -> Result<Vec<i64>, String> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|x| !x.is_empty())
+        .map(|x| x.parse().map_err(|e| format!("bad number {}: {}", x, e)))
+        .collect()
+}
+
+/// Runs the case on a computer and returns a description of all
+/// mismatches, if any.
+pub fn run_case(case: &Case, comp: &mut Computer) -> Result<(), String> {
+    comp.load_memory(&case.program);
+    let mut inputs = case.input.iter();
+    let mut output = vec![];
+
+    comp.run_as_coroutine();
+    loop {
+        match comp.status {
+            Status::RequiresInput => match inputs.next() {
+                Some(&value) => comp.send_input(value),
+                None => {
+                    return Err(format!(
+                        "program requires more than {} inputs",
+                        case.input.len()
+                    ))
+                }
+            },
+            Status::ProducedOutput(out) => {
+                output.push(out);
+                comp.run_as_coroutine();
+            }
+            Status::Halted => break,
+            Status::Faulted(ref fault) => return Err(format!("faulted: {}", fault)),
+        }
+    }
+
+    let mut diffs = vec![];
+    if let Some(ref expected) = case.output {
+        if let Some(diff) = diff("output", expected, &output) {
+            diffs.push(diff);
+        }
+    }
+    if let Some(ref expected) = case.memory {
+        if let Some(diff) = diff("memory", expected, comp.dump_memory()) {
+            diffs.push(diff);
+        }
+    }
+
+    if diffs.is_empty() {
+        Ok(())
+    } else {
+        Err(diffs.join("\n"))
+    }
+}
+
+/// Runs every case in `dir` on a fresh computer from `new_computer`.
+pub fn run_all<P, F>(dir: P, mut new_computer: F) -> io::Result<Report>
+where
+    P: AsRef<Path>,
+    F: FnMut() -> Computer,
+{
+    let mut report = Report::default();
+    for case in discover(dir)? {
+        match run_case(&case, &mut new_computer()) {
+            Ok(()) => report.passed.push(case.name),
+            Err(diff) => report.failed.push((case.name, diff)),
+        }
+    }
+    Ok(report)
+}
+
+fn diff(what: &str, expected: &[i64], actual: &[i64]) -> Option<String> {
+    const MAX_SHOWN: usize = 10;
+
+    if expected == actual {
+        return None;
+    }
+
+    let mut lines = vec![format!(
+        "  {}: expected {} values, got {}",
+        what,
+        expected.len(),
+        actual.len()
+    )];
+    let len = expected.len().max(actual.len());
+    let changed: Vec<usize> = (0..len)
+        .filter(|&ix| expected.get(ix) != actual.get(ix))
+        .collect();
+    let show = |v: Option<&i64>| v.map_or("-".to_owned(), |x| x.to_string());
+    for &ix in changed.iter().take(MAX_SHOWN) {
+        lines.push(format!(
+            "    [{}] expected {}, got {}",
+            ix,
+            show(expected.get(ix)),
+            show(actual.get(ix))
+        ));
+    }
+    if changed.len() > MAX_SHOWN {
+        lines.push(format!("    ... and {} more", changed.len() - MAX_SHOWN));
+    }
+    Some(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_golden_files() {
+        let report = run_all("testdata/intcode", Computer::new).unwrap();
+        assert!(report.is_ok(), "{}", report);
+        assert!(!report.passed.is_empty());
+    }
+
+    #[test]
+    fn test_diff() {
+        let case = Case {
+            name: "echo".to_owned(),
+            program: vec![3, 0, 4, 0, 99],
+            input: vec![5],
+            output: Some(vec![6]),
+            memory: Some(vec![5, 0, 4, 0, 99]),
+        };
+        assert_eq!(
+            run_case(&case, &mut Computer::new()),
+            Err([
+                "  output: expected 1 values, got 1",
+                "    [0] expected 6, got 5",
+            ]
+            .join("\n"))
+        );
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use super::disasm::{disassemble_at, Line};
+use super::*;
+
+/// Executed instructions and outcomes of conditional jumps, collected
+/// over one or more runs.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Coverage {
+    hits: Vec<u64>,
+    branches: BTreeMap<usize, Branch>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Branch {
+    pub taken: u64,
+    pub not_taken: u64,
+}
+
+impl Branch {
+    fn is_partial(&self) -> bool {
+        self.taken == 0 || self.not_taken == 0
+    }
+}
+
+/// Coverage of a single disassembly line.
+pub struct ReportLine {
+    pub line: Line,
+    pub hits: u64,
+    pub branch: Option<Branch>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Coverage::default()
+    }
+
+    pub fn hit(&mut self, addr: usize) {
+        if addr >= self.hits.len() {
+            self.hits.resize(addr + 1, 0);
+        }
+        self.hits[addr] += 1;
+    }
+
+    pub fn branch(&mut self, addr: usize, taken: bool) {
+        let branch = self.branches.entry(addr).or_default();
+        if taken {
+            branch.taken += 1;
+        } else {
+            branch.not_taken += 1;
+        }
+    }
+
+    pub fn hits(&self, addr: usize) -> u64 {
+        self.hits.get(addr).cloned().unwrap_or(0)
+    }
+
+    pub fn merge(&mut self, other: &Coverage) {
+        if other.hits.len() > self.hits.len() {
+            self.hits.resize(other.hits.len(), 0);
+        }
+        for (addr, hits) in other.hits.iter().enumerate() {
+            self.hits[addr] += hits;
+        }
+        for (&addr, branch) in other.branches.iter() {
+            let ours = self.branches.entry(addr).or_default();
+            ours.taken += branch.taken;
+            ours.not_taken += branch.not_taken;
+        }
+    }
+
+    /// Disassembly of `mem` annotated with coverage. Executed addresses
+    /// always start a new line, so code which is only reached by jumping
+    /// into the middle of another instruction is still shown.
+    pub fn annotate(&self, mem: &[i64], ops: &InstructionSet) -> Vec<ReportLine> {
+        let mut lines = vec![];
+        let mut addr = 0;
+        while addr < mem.len() {
+            let mut line = disassemble_at(mem, addr, ops);
+            if (addr + 1..addr + line.len).any(|a| self.hits(a) > 0) {
+                line = Line::data(addr, mem[addr]);
+            }
+            addr += line.len;
+            lines.push(ReportLine {
+                hits: self.hits(line.addr),
+                branch: self.branches.get(&line.addr).cloned(),
+                line,
+            });
+        }
+        lines
+    }
+
+    pub fn text_report(&self, mem: &[i64], ops: &InstructionSet) -> String {
+        let lines = self.annotate(mem, ops);
+        let mut report = summary(&lines);
+        report.push('\n');
+        for l in lines {
+            let count = match (l.hits, l.line.is_data()) {
+                (0, true) => String::new(),
+                (0, false) => "#####".to_owned(),
+                (hits, _) => hits.to_string(),
+            };
+            write!(report, "{:>9} | {}", count, l.line).unwrap();
+            if let Some(b) = l.branch {
+                write!(report, "  [taken {}, not taken {}]", b.taken, b.not_taken).unwrap();
+            }
+            report.push('\n');
+        }
+        report
+    }
+
+    pub fn html_report(&self, mem: &[i64], ops: &InstructionSet) -> String {
+        let lines = self.annotate(mem, ops);
+        let mut html = String::new();
+        html.push_str("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str("<title>Intcode coverage</title>\n<style>\n");
+        html.push_str("body { font-family: monospace; }\n");
+        html.push_str("td { padding: 0 1em; white-space: pre; }\n");
+        html.push_str(".hit { background: #dfd; }\n");
+        html.push_str(".miss { background: #fdd; }\n");
+        html.push_str(".partial { background: #ffc; }\n");
+        html.push_str("</style>\n</head>\n<body>\n");
+        writeln!(html, "<p>{}</p>", escape(&summary(&lines))).unwrap();
+        html.push_str("<table>\n");
+        for l in lines {
+            let class = match (l.hits, l.branch) {
+                (0, _) if l.line.is_data() => "",
+                (0, _) => "miss",
+                (_, Some(b)) if b.is_partial() => "partial",
+                _ => "hit",
+            };
+            let branch = l
+                .branch
+                .map(|b| format!("taken {}, not taken {}", b.taken, b.not_taken))
+                .unwrap_or_default();
+            writeln!(
+                html,
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                class,
+                if l.hits > 0 {
+                    l.hits.to_string()
+                } else {
+                    String::new()
+                },
+                l.line.addr,
+                escape(&l.line.text),
+                branch
+            )
+            .unwrap();
+        }
+        html.push_str("</table>\n</body>\n</html>\n");
+        html
+    }
+}
+
+fn summary(lines: &[ReportLine]) -> String {
+    let code: Vec<&ReportLine> = lines.iter().filter(|l| !l.line.is_data()).collect();
+    let executed = code.iter().filter(|l| l.hits > 0).count();
+    let branches: Vec<Branch> = lines.iter().filter_map(|l| l.branch).collect();
+    let outcomes: usize = branches
+        .iter()
+        .map(|b| (b.taken > 0) as usize + (b.not_taken > 0) as usize)
+        .sum();
+    format!(
+        "instructions: {}/{} executed, branch outcomes: {}/{} seen",
+        executed,
+        code.len(),
+        outcomes,
+        branches.len() * 2
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs 1 if input is zero, otherwise halts silently.
+    const PROGRAM: &[i64] = &[3, 9, 1005, 9, 8, 104, 1, 99, 99, 0];
+
+    fn run(input: &str) -> Coverage {
+        let mut comp = Computer::new();
+        comp.start_coverage();
+        comp.set_input_lines(&[input]);
+        comp.run_with_memory(PROGRAM);
+        comp.take_coverage().unwrap()
+    }
+
+    #[test]
+    fn test_coverage() {
+        let zero = run("0");
+        assert_eq!(zero.hits(0), 1);
+        assert_eq!(zero.hits(5), 1);
+        assert_eq!(zero.hits(8), 0);
+
+        let mut merged = run("5");
+        assert_eq!(merged.hits(5), 0);
+        merged.merge(&zero);
+        assert_eq!(merged.hits(0), 2);
+        assert_eq!(merged.hits(5), 1);
+        assert_eq!(merged.hits(8), 1);
+
+        let report = zero.text_report(PROGRAM, &InstructionSet::new());
+        assert_eq!(
+            report,
+            [
+                "instructions: 4/5 executed, branch outcomes: 1/2 seen",
+                "        1 |     0: in [9]",
+                "        1 |     2: jt [9], #8  [taken 0, not taken 1]",
+                "        1 |     5: out #1",
+                "        1 |     7: halt",
+                "    ##### |     8: halt",
+                "          |     9: data 0",
+                "",
+            ]
+            .join("\n")
+        );
+    }
+}
@@ -0,0 +1,185 @@
+//! Devices which can be mapped into `Computer` memory.
+//!
+//! Devices are moved into the computer, so the ones with state the host
+//! wants to look at share it through `Rc`: keep a clone of the device
+//! before mapping it.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::time::Instant;
+
+use super::*;
+
+/// Milliseconds since the clock was created, writes are ignored.
+pub struct Clock {
+    start: Instant,
+}
+
+impl Clock {
+    pub fn new() -> Self {
+        Clock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Default for Clock {
+    fn default() -> Self {
+        Clock::new()
+    }
+}
+
+impl Device for Clock {
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.start.elapsed().as_millis() as i64
+    }
+
+    fn write(&mut self, _offset: usize, _value: i64) {}
+}
+
+/// Xorshift generator, every read returns a new non-negative number and
+/// a write reseeds it.
+pub struct Random {
+    state: u64,
+}
+
+impl Random {
+    pub fn new(seed: i64) -> Self {
+        let mut random = Random { state: 0 };
+        random.write(0, seed);
+        random
+    }
+}
+
+impl Device for Random {
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        (self.state >> 1) as i64
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        // Zero is a fixed point of xorshift.
+        self.state = (value as u64) | 1;
+    }
+}
+
+/// Single word ASCII console: writes append a character to `text`, reads
+/// take the next queued input character or -1 if there's none.
+#[derive(Clone, Default)]
+pub struct Console {
+    text: Rc<RefCell<String>>,
+    input: Rc<RefCell<VecDeque<u8>>>,
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Console::default()
+    }
+
+    pub fn text(&self) -> String {
+        self.text.borrow().clone()
+    }
+
+    pub fn type_in(&self, s: &str) {
+        self.input.borrow_mut().extend(s.bytes());
+    }
+}
+
+impl Device for Console {
+    fn read(&mut self, _offset: usize) -> i64 {
+        self.input.borrow_mut().pop_front().map_or(-1, i64::from)
+    }
+
+    fn write(&mut self, _offset: usize, value: i64) {
+        self.text.borrow_mut().push(value as u8 as char);
+    }
+}
+
+/// Row major `width` x `height` words of pixels.
+#[derive(Clone)]
+pub struct Framebuffer {
+    width: usize,
+    height: usize,
+    pixels: Rc<RefCell<Vec<i64>>>,
+}
+
+impl Framebuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Framebuffer {
+            width,
+            height,
+            pixels: Rc::new(RefCell::new(vec![0; width * height])),
+        }
+    }
+
+    /// Number of words to map the framebuffer to.
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pixel(&self, x: usize, y: usize) -> i64 {
+        self.pixels.borrow()[y * self.width + x]
+    }
+}
+
+impl Device for Framebuffer {
+    fn read(&mut self, offset: usize) -> i64 {
+        self.pixels.borrow()[offset]
+    }
+
+    fn write(&mut self, offset: usize, value: i64) {
+        self.pixels.borrow_mut()[offset] = value;
+    }
+}
+
+impl fmt::Display for Framebuffer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                write!(f, "{}", if self.pixel(x, y) != 0 { '#' } else { ' ' })?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_devices() {
+        let console = Console::new();
+        console.type_in("AB");
+        let screen = Framebuffer::new(2, 2);
+        let mut comp = Computer::new();
+        comp.map_device(1000..1001, console.clone());
+        comp.map_device(2000..2000 + screen.len(), screen.clone());
+        comp.map_device(3000..3001, Random::new(42));
+
+        #[rustfmt::skip]
+        let mem = comp.run_with_memory([
+            1001, 1000, 1, 1000,    // add [1000], #1, [1000]
+            1101, 0, 1, 2003,       // add #0, #1, [2003]
+            1, 3000, 3000, 20,      // add [3000], [3000], [20]
+            1, 1000, 2003, 19,      // add [1000], [2003], [19]
+            99, 0, 0, 0, 0,
+        ])
+        .to_vec();
+
+        assert_eq!(console.text(), "B");
+        assert_eq!(screen.pixel(1, 1), 1);
+        assert_eq!(screen.to_string(), "  \n #\n");
+        assert_eq!(mem[19], 'C' as i64);
+        assert_ne!(mem[20], 0);
+        assert_eq!(mem.len(), 21);
+    }
+}
@@ -0,0 +1,161 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use super::disasm::{disassemble, Line};
+use super::patch::Patch;
+use super::*;
+
+/// A run of consecutive words which differ between two memory images.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Change {
+    pub start: usize,
+    pub before: Vec<i64>,
+    pub after: Vec<i64>,
+}
+
+impl Change {
+    pub fn range(&self) -> Range<usize> {
+        self.start..self.start + self.after.len()
+    }
+}
+
+/// Changed ranges between two images, words past the end of the shorter
+/// image count as zeroes.
+pub fn diff(before: &[i64], after: &[i64]) -> Vec<Change> {
+    let word = |mem: &[i64], a: usize| mem.get(a).cloned().unwrap_or(0);
+    let mut changes: Vec<Change> = vec![];
+    for addr in 0..before.len().max(after.len()) {
+        let (old, new) = (word(before, addr), word(after, addr));
+        if old == new {
+            continue;
+        }
+        match changes.last_mut() {
+            Some(change) if change.range().end == addr => {
+                change.before.push(old);
+                change.after.push(new);
+            }
+            _ => changes.push(Change {
+                start: addr,
+                before: vec![old],
+                after: vec![new],
+            }),
+        }
+    }
+    changes
+}
+
+/// Patch which turns the `before` image of `changes` into the `after`
+/// one.
+pub fn to_patch(changes: &[Change]) -> Patch {
+    let mut patch = Patch::new();
+    for change in changes {
+        for (ix, &value) in change.after.iter().enumerate() {
+            patch = patch.set(change.start + ix, value);
+        }
+    }
+    patch
+}
+
+/// Unified diff style report: for every changed range the disassembly
+/// lines covering it in both images, with `context` unchanged lines
+/// around them.
+pub fn report(before: &[i64], after: &[i64], ops: &InstructionSet, context: usize) -> String {
+    let old_lines = disassemble(before, ops);
+    let new_lines = disassemble(after, ops);
+    let mut report = String::new();
+    for change in diff(before, after) {
+        let range = change.range();
+        writeln!(
+            report,
+            "@@ {}..{} ({} words) @@",
+            range.start,
+            range.end,
+            range.len()
+        )
+        .unwrap();
+        let (old, changed) = overlapping(&old_lines, &range);
+        let (new, _) = overlapping(&new_lines, &range);
+        for line in &old_lines[changed.start.saturating_sub(context)..changed.start] {
+            writeln!(report, "  {}", line).unwrap();
+        }
+        for line in old {
+            writeln!(report, "- {}", line).unwrap();
+        }
+        for line in new {
+            writeln!(report, "+ {}", line).unwrap();
+        }
+        let end = (changed.end + context).min(old_lines.len());
+        for line in &old_lines[changed.end..end] {
+            writeln!(report, "  {}", line).unwrap();
+        }
+    }
+    report
+}
+
+/// Lines which overlap `range` and their indices in `lines`.
+fn overlapping<'a>(lines: &'a [Line], range: &Range<usize>) -> (&'a [Line], Range<usize>) {
+    let start = lines
+        .iter()
+        .position(|l| l.addr + l.len > range.start)
+        .unwrap_or(lines.len());
+    let end = lines[start..]
+        .iter()
+        .position(|l| l.addr >= range.end)
+        .map_or(lines.len(), |ix| start + ix);
+    (&lines[start..end], start..end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff() {
+        let before = vec![1, 0, 0, 3, 2, 3, 11, 0, 99, 30];
+        let after = vec![1, 9, 9, 3, 2, 3, 11, 0, 99, 30, 0, 7];
+        let changes = diff(&before, &after);
+        assert_eq!(
+            changes,
+            vec![
+                Change {
+                    start: 1,
+                    before: vec![0, 0],
+                    after: vec![9, 9]
+                },
+                Change {
+                    start: 11,
+                    before: vec![0],
+                    after: vec![7]
+                }
+            ]
+        );
+
+        let mut patched = before.clone();
+        to_patch(&changes).apply(&mut patched).unwrap();
+        assert_eq!(patched, after);
+
+        let report = report(&before, &after, &InstructionSet::new(), 1);
+        assert_eq!(
+            report,
+            "@@ 1..3 (2 words) @@\n\
+             -     0: add [0], [0], [3]\n\
+             +     0: add [9], [9], [3]\n\
+             \x20\x20    4: mul [3], [11], [0]\n\
+             @@ 11..12 (1 words) @@\n\
+             \x20\x20    9: data 30\n\
+             +    11: lt [0], [0], [0]\n"
+        );
+    }
+
+    #[test]
+    fn test_day2_alarm() {
+        let program = Computer::read_program("input/day2.txt");
+        let patch = Patch::load("input/day2-alarm.patch").unwrap();
+        let mut patched = program.clone();
+        patch.apply(&mut patched).unwrap();
+        assert_eq!(to_patch(&diff(&program, &patched)), patch);
+
+        let mut comp = Computer::new();
+        assert_eq!(comp.run_with_memory(&patched)[0], 3166704);
+    }
+}
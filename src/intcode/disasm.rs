@@ -0,0 +1,156 @@
+use std::fmt;
+
+use super::*;
+
+/// One line of disassembly: either a decoded instruction or a memory
+/// cell which doesn't decode and is shown as raw data.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Line {
+    pub addr: usize,
+    pub len: usize,
+    pub kind: Kind,
+    pub text: String,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Kind {
+    Instruction,
+    Data,
+}
+
+impl Line {
+    pub fn data(addr: usize, value: i64) -> Self {
+        Line {
+            addr,
+            len: 1,
+            kind: Kind::Data,
+            text: format!("data {}", value),
+        }
+    }
+
+    pub fn is_data(&self) -> bool {
+        self.kind == Kind::Data
+    }
+}
+
+impl fmt::Display for Line {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:>5}: {}", self.addr, self.text)
+    }
+}
+
+/// Linear sweep over the whole memory image.
+pub fn disassemble(mem: &[i64], ops: &InstructionSet) -> Vec<Line> {
+    let mut lines = vec![];
+    let mut addr = 0;
+    while addr < mem.len() {
+        let line = disassemble_at(mem, addr, ops);
+        addr += line.len;
+        lines.push(line);
+    }
+    lines
+}
+
+pub fn disassemble_at(mem: &[i64], addr: usize, ops: &InstructionSet) -> Line {
+    let word = |a: usize| mem.get(a).cloned().unwrap_or(0);
+    match Instruction::decode(word(addr), ops) {
+        Ok(instruction) => {
+            let operands: Vec<String> = instruction
+                .modes()
+                .into_iter()
+                .enumerate()
+                .map(|(ix, mode)| operand(mode, word(addr + 1 + ix)))
+                .collect();
+            let mut text = instruction.mnemonic(ops).to_owned();
+            if !operands.is_empty() {
+                text.push(' ');
+                text.push_str(&operands.join(", "));
+            }
+            Line {
+                addr,
+                len: instruction.len(),
+                kind: Kind::Instruction,
+                text,
+            }
+        }
+        Err(_) => Line::data(addr, word(addr)),
+    }
+}
+
+fn operand(mode: ParameterMode, value: i64) -> String {
+    match mode {
+        ParameterMode::Position => format!("[{}]", value),
+        ParameterMode::Immediate => format!("#{}", value),
+        ParameterMode::Relative if value < 0 => format!("[rb-{}]", -value),
+        ParameterMode::Relative => format!("[rb+{}]", value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Print;
+
+    impl CustomOp for Print {
+        fn mnemonic(&self) -> &str {
+            "print"
+        }
+
+        fn params(&self) -> &[Param] {
+            &[Param::Read, Param::Read]
+        }
+
+        fn execute(&mut self, _args: &mut [i64]) -> Effect {
+            Effect::Continue
+        }
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let mut ops = InstructionSet::new();
+        ops.register(42, Print);
+
+        let lines: Vec<String> = disassemble(&[1002, 4, 3, 4, 209, -1, 142, 7, 0, 99, 77], &ops)
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+
+        assert_eq!(
+            lines,
+            vec![
+                "    0: mul [4], #3, [4]",
+                "    4: arb [rb-1]",
+                "    6: print #7, [0]",
+                "    9: halt",
+                "   10: data 77",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_custom_data_op() {
+        struct Data;
+
+        impl CustomOp for Data {
+            fn mnemonic(&self) -> &str {
+                "data"
+            }
+
+            fn params(&self) -> &[Param] {
+                &[Param::Read]
+            }
+
+            fn execute(&mut self, _args: &mut [i64]) -> Effect {
+                Effect::Continue
+            }
+        }
+
+        let mut ops = InstructionSet::new();
+        ops.register(42, Data);
+        let lines = disassemble(&[142, 5, 77], &ops);
+        assert_eq!(lines[0].to_string(), "    0: data #5");
+        assert!(!lines[0].is_data());
+        assert!(lines[1].is_data());
+    }
+}
@@ -0,0 +1,458 @@
+//! Peephole optimizer for Intcode programs.
+//!
+//! Intcode code and data share memory, so an instruction can only be
+//! rewritten when nothing else can observe the change: its words must
+//! not be written or read by the program and no jump may land in the
+//! middle of it. Words which are never executed, read or written can
+//! be dropped (zeroed) altogether. All of this is decided by a static
+//! walk over the code reachable from address 0.
+//!
+//! Relative mode writes and reads, as well as jumps to computed
+//! addresses, can't be resolved statically. By default they make the
+//! optimizer give up on the whole program. `Options::assume_structured`
+//! relaxes that for code produced by the AoC compiler, assuming that
+//! relative mode addressing (the stack) never touches the program image
+//! and that computed jumps only go to addresses which appear in the
+//! program as immediate operands (return addresses pushed by calls).
+
+use std::collections::BTreeMap;
+use std::collections::HashSet;
+
+use super::*;
+
+#[derive(Debug, Default, Clone)]
+pub struct Options {
+    pub assume_structured: bool,
+}
+
+#[derive(PartialEq, Eq, Debug, Default, Clone)]
+pub struct Report {
+    /// Arithmetic on two immediates turned into constant stores.
+    pub folded: usize,
+    /// Conditional jumps on an immediate condition turned into `jt #1`.
+    pub unconditional: usize,
+    /// Jumps retargeted past another unconditional jump.
+    pub threaded: usize,
+    /// Unreachable instructions removed from the program.
+    pub removed: usize,
+}
+
+impl Report {
+    /// Instructions which don't have to be executed or kept anymore.
+    pub fn saved(&self) -> usize {
+        self.threaded + self.removed
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} instructions saved ({} jumps threaded, {} unreachable removed), \
+             {} constants folded, {} jumps made unconditional",
+            self.saved(),
+            self.threaded,
+            self.removed,
+            self.folded,
+            self.unconditional
+        )
+    }
+}
+
+#[derive(Default)]
+struct Analysis {
+    code: BTreeMap<usize, Instruction>,
+    written: HashSet<usize>,
+    read: HashSet<usize>,
+    targets: HashSet<usize>,
+    /// Length of the program image.
+    size: usize,
+    unknown_writes: bool,
+    unknown_reads: bool,
+    unknown_jumps: bool,
+}
+
+impl Analysis {
+    fn may_write(&self, addr: usize) -> bool {
+        self.unknown_writes || self.written.contains(&addr)
+    }
+
+    fn may_read(&self, addr: usize) -> bool {
+        self.unknown_reads || self.read.contains(&addr)
+    }
+
+    /// Whether the instruction at `addr` is guaranteed to stay as it is
+    /// and can be changed without anybody noticing.
+    fn is_rewritable(&self, addr: usize) -> bool {
+        let len = match self.code.get(&addr) {
+            Some(instruction) => instruction.len(),
+            None => return false,
+        };
+        // A trailing instruction cut short has no words to rewrite.
+        !self.unknown_jumps
+            && addr + len <= self.size
+            && (addr..addr + len).all(|a| !self.may_write(a) && !self.may_read(a))
+            && (addr + 1..addr + len).all(|a| !self.targets.contains(&a))
+    }
+}
+
+pub fn optimize(mem: &[i64], ops: &InstructionSet, options: &Options) -> (Vec<i64>, Report) {
+    let analysis = analyze(mem, ops, options);
+    let mut optimized = mem.to_vec();
+    let mut report = Report::default();
+
+    for (&addr, instruction) in analysis.code.iter() {
+        if !analysis.is_rewritable(addr) {
+            continue;
+        }
+
+        match *instruction {
+            Instruction::Add(ParameterMode::Immediate, ParameterMode::Immediate, out)
+            | Instruction::Mul(ParameterMode::Immediate, ParameterMode::Immediate, out) => {
+                let (a, b) = (word(mem, addr + 1), word(mem, addr + 2));
+                let value = match instruction {
+                    Instruction::Add(..) => a + b,
+                    _ => a * b,
+                };
+                optimized[addr] = 1101 + out.digit() * 10_000;
+                optimized[addr + 1] = value;
+                optimized[addr + 2] = 0;
+                report.folded += 1;
+            }
+            Instruction::JumpIfTrue(ParameterMode::Immediate, target)
+            | Instruction::JumpIfFalse(ParameterMode::Immediate, target) => {
+                let canonical = 105 + target.digit() * 1000;
+                if jump_taken(mem, addr, instruction) == Some(true)
+                    && (word(mem, addr), word(mem, addr + 1)) != (canonical, 1)
+                {
+                    optimized[addr] = canonical;
+                    optimized[addr + 1] = 1;
+                    report.unconditional += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    for &addr in analysis.code.keys() {
+        if !analysis.is_rewritable(addr) {
+            continue;
+        }
+        if let Some(first) = direct_jump(mem, &analysis, addr) {
+            let mut target = first;
+            let mut seen = HashSet::new();
+            while seen.insert(target) {
+                match direct_jump(mem, &analysis, target) {
+                    Some(next) if is_stable(&analysis, target) => target = next,
+                    _ => break,
+                }
+            }
+            if target != first {
+                optimized[addr + 2] = target as i64;
+                report.threaded += 1;
+            }
+        }
+    }
+
+    if !analysis.unknown_jumps {
+        let mut executed = vec![false; mem.len()];
+        for (&addr, instruction) in analysis.code.iter() {
+            let end = (addr + instruction.len()).min(mem.len());
+            executed[addr..end].iter_mut().for_each(|e| *e = true);
+        }
+        let is_dead = |a: usize| !executed[a] && !analysis.may_read(a) && !analysis.may_write(a);
+
+        let mut addr = 0;
+        while addr < mem.len() {
+            if !is_dead(addr) || mem[addr] == 0 {
+                addr += 1;
+                continue;
+            }
+            // Only count dead words which decode as a whole instruction,
+            // anything else is leftover data.
+            let len = match Instruction::decode(mem[addr], ops) {
+                Ok(instruction)
+                    if (addr..addr + instruction.len()).all(|a| a < mem.len() && is_dead(a)) =>
+                {
+                    report.removed += 1;
+                    instruction.len()
+                }
+                _ => 1,
+            };
+            optimized[addr..addr + len].iter_mut().for_each(|w| *w = 0);
+            addr += len;
+        }
+    }
+
+    (optimized, report)
+}
+
+fn analyze(mem: &[i64], ops: &InstructionSet, options: &Options) -> Analysis {
+    let word = |a: usize| word(mem, a);
+    let mut analysis = Analysis {
+        size: mem.len(),
+        ..Analysis::default()
+    };
+    let mut work = vec![0];
+    let mut immediates = HashSet::new();
+    let mut has_indirect_jumps = false;
+    let mut undecodable = vec![];
+
+    loop {
+        while let Some(addr) = work.pop() {
+            if addr >= mem.len() || analysis.code.contains_key(&addr) {
+                continue;
+            }
+            let instruction = match Instruction::decode(word(addr), ops) {
+                Ok(instruction) => instruction,
+                Err(_) => {
+                    undecodable.push(addr);
+                    continue;
+                }
+            };
+
+            let params = instruction.params(ops);
+            for (ix, (param, mode)) in params.iter().zip(instruction.modes()).enumerate() {
+                let raw = word(addr + 1 + ix);
+                match (param, mode) {
+                    (_, ParameterMode::Relative) if options.assume_structured => {}
+                    (Param::Read, ParameterMode::Relative) => analysis.unknown_reads = true,
+                    (Param::Write, ParameterMode::Relative) => analysis.unknown_writes = true,
+                    (Param::Read, ParameterMode::Position) => {
+                        analysis.read.insert(raw as usize);
+                    }
+                    (Param::Write, ParameterMode::Position) => {
+                        analysis.written.insert(raw as usize);
+                    }
+                    (_, ParameterMode::Immediate) => {
+                        immediates.insert(raw as usize);
+                    }
+                }
+            }
+
+            let next = addr + instruction.len();
+            match instruction {
+                Instruction::Stop => {}
+                Instruction::JumpIfTrue(_, target_mode)
+                | Instruction::JumpIfFalse(_, target_mode) => {
+                    let taken = jump_taken(mem, addr, &instruction);
+                    if taken != Some(false) {
+                        if target_mode == ParameterMode::Immediate {
+                            let target = word(addr + 2) as usize;
+                            analysis.targets.insert(target);
+                            work.push(target);
+                        } else {
+                            has_indirect_jumps = true;
+                        }
+                    }
+                    if taken != Some(true) {
+                        work.push(next);
+                    }
+                }
+                Instruction::Custom(..) => {
+                    // Custom instructions may jump anywhere.
+                    analysis.unknown_jumps = true;
+                    work.push(next);
+                }
+                _ => work.push(next),
+            }
+            analysis.code.insert(addr, instruction);
+        }
+
+        // With structured code assumed, computed jumps are returns from
+        // calls: they land right after a jump, at an address which the
+        // caller mentions as an immediate when pushing it on the stack.
+        if has_indirect_jumps && options.assume_structured {
+            let targets = &mut analysis.targets;
+            let new: Vec<usize> = analysis
+                .code
+                .iter()
+                .filter(|(_, i)| is_jump(i))
+                .map(|(&addr, i)| addr + i.len())
+                .filter(|a| immediates.contains(a) && targets.insert(*a))
+                .collect();
+            if new.is_empty() {
+                break;
+            }
+            work.extend(new);
+        } else {
+            break;
+        }
+    }
+
+    if has_indirect_jumps && !options.assume_structured {
+        analysis.unknown_jumps = true;
+    }
+
+    // Code which is overwritten at runtime may turn into anything.
+    let overwritten = analysis
+        .code
+        .iter()
+        .any(|(&addr, i)| (addr..addr + i.len()).any(|a| analysis.may_write(a)));
+    if overwritten || undecodable.iter().any(|&a| analysis.may_write(a)) {
+        analysis.unknown_jumps = true;
+    }
+
+    analysis
+}
+
+fn is_jump(instruction: &Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::JumpIfTrue(..) | Instruction::JumpIfFalse(..)
+    )
+}
+
+/// Word at `addr`, missing words past the end of the program read as 0
+/// like they do in the computer.
+fn word(mem: &[i64], addr: usize) -> i64 {
+    mem.get(addr).cloned().unwrap_or(0)
+}
+
+/// Whether a jump with an immediate condition is always or never taken.
+fn jump_taken(mem: &[i64], addr: usize, instruction: &Instruction) -> Option<bool> {
+    match instruction {
+        Instruction::JumpIfTrue(ParameterMode::Immediate, _) => Some(word(mem, addr + 1) != 0),
+        Instruction::JumpIfFalse(ParameterMode::Immediate, _) => Some(word(mem, addr + 1) == 0),
+        _ => None,
+    }
+}
+
+/// Target of an unconditional jump to an immediate address.
+fn direct_jump(mem: &[i64], analysis: &Analysis, addr: usize) -> Option<usize> {
+    match analysis.code.get(&addr) {
+        Some(i @ Instruction::JumpIfTrue(_, ParameterMode::Immediate))
+        | Some(i @ Instruction::JumpIfFalse(_, ParameterMode::Immediate))
+            if jump_taken(mem, addr, i) == Some(true) =>
+        {
+            Some(word(mem, addr + 2) as usize)
+        }
+        _ => None,
+    }
+}
+
+fn is_stable(analysis: &Analysis, addr: usize) -> bool {
+    let len = analysis.code.get(&addr).map_or(0, |i| i.len());
+    !analysis.unknown_jumps && (addr..addr + len).all(|a| !analysis.may_write(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::cell::Cell;
+
+    fn run(mem: &[i64], inputs: &[i64]) -> Vec<i64> {
+        let mut comp = Computer::new();
+        comp.load_memory(mem);
+        let ix = Cell::new(0);
+        let input = || {
+            let value = inputs.get(ix.get()).cloned().unwrap_or(0);
+            ix.set(ix.get() + 1);
+            value
+        };
+        comp.outputs_with_input(input)
+            .map(|out| out.expect("computer faulted"))
+            .collect()
+    }
+
+    #[test]
+    fn test_optimize() {
+        #[rustfmt::skip]
+        let program = vec![
+            1102, 6, 7, 23,    // 0: mul #6, #7, [23]
+            4, 23,             // 4: out [23]
+            1006, 24, 15,      // 6: jf [24], #15
+            1106, 0, 12,       // 9: jf #0, #12
+            1105, 1, 18,       // 12: jt #1, #18
+            1105, 1, 12,       // 15: jt #1, #12
+            99,                // 18: halt
+            1101, 1, 1, 20,    // 19: unreachable
+            0, 0,              // 23: data
+        ];
+
+        let (optimized, report) = optimize(&program, &InstructionSet::new(), &Options::default());
+        assert_eq!(
+            report,
+            Report {
+                folded: 1,
+                unconditional: 1,
+                threaded: 2,
+                removed: 1,
+            }
+        );
+        #[rustfmt::skip]
+        assert_eq!(optimized, vec![
+            1101, 42, 0, 23, 4, 23, 1006, 24, 15, 1105, 1, 18, 1105, 1, 18, 1105, 1, 18, 99,
+            0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(run(&optimized, &[]), run(&program, &[]));
+    }
+
+    #[test]
+    fn test_remove_unreachable() {
+        let program = vec![104, 1, 99, 1101, 2, 2, 7, 99];
+        let (optimized, report) = optimize(&program, &InstructionSet::new(), &Options::default());
+        assert_eq!(report.removed, 2);
+        assert_eq!(optimized, vec![104, 1, 99, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_unknown_writes() {
+        // Relative writes may hit any address, so nothing is proven.
+        let program = vec![109, 1, 21101, 2, 3, 5, 1105, 1, 9, 99];
+        let (optimized, report) = optimize(&program, &InstructionSet::new(), &Options::default());
+        assert_eq!(report, Report::default());
+        assert_eq!(optimized, program);
+    }
+
+    #[test]
+    fn test_truncated_instruction() {
+        // The computer reads the missing words as 0.
+        for program in &[vec![1105, 1, 3, 1105], vec![1105, 1, 3, 1101, 6]] {
+            let (optimized, report) =
+                optimize(program, &InstructionSet::new(), &Options::default());
+            assert_eq!(report, Report::default());
+            assert_eq!(&optimized, program);
+        }
+    }
+
+    #[test]
+    fn test_equivalence_on_inputs() {
+        let inputs: &[(&str, &[i64])] = &[
+            ("input/day5.txt", &[1]),
+            ("input/day5.txt", &[5]),
+            ("input/day7.txt", &[3, 0]),
+            ("input/day7.txt", &[7, 0, 1, 2, 3, 4, 5, 6, 7, 8]),
+            ("input/day9.txt", &[1]),
+            ("input/day11.txt", &[0, 1, 1, 0, 1, 0, 0, 1, 1]),
+            ("input/day13.txt", &[]),
+        ];
+
+        let mut rewritten = 0;
+        for options in &[
+            Options::default(),
+            Options {
+                assume_structured: true,
+            },
+        ] {
+            for (path, input) in inputs {
+                let program = Computer::read_program(path);
+                let (optimized, _) = optimize(&program, &InstructionSet::new(), options);
+                assert_eq!(run(&optimized, input), run(&program, input), "{}", path);
+                rewritten += (optimized != program) as usize;
+            }
+
+            // Day 2 has no outputs, its answer is left at address 0.
+            let mut program = Computer::read_program("input/day2.txt");
+            program[1..3].copy_from_slice(&[12, 2]);
+            let (optimized, _) = optimize(&program, &InstructionSet::new(), options);
+            let mut comp = Computer::new();
+            let expected = comp.run_with_memory(&program)[0];
+            assert_eq!(comp.run_with_memory(&optimized)[0], expected);
+            rewritten += (optimized != program) as usize;
+        }
+        // Otherwise nothing was compared.
+        assert!(rewritten > 0);
+    }
+}
@@ -0,0 +1,146 @@
+use std::iter;
+
+use super::*;
+
+/// Outputs of a running computer as an iterator.
+///
+/// Every call to `next` resumes the computer until it produces the
+/// next output. When the program asks for input, the callback given to
+/// `Computer::outputs_with_input` is used; without a callback the run
+/// stops with `Fault::NoInput`. The iterator ends when the program
+/// halts, right after yielding a fault.
+pub struct Outputs<'a> {
+    comp: &'a mut Computer,
+    input: Option<Box<dyn FnMut() -> i64 + 'a>>,
+    done: bool,
+}
+
+impl Computer {
+    pub fn outputs(&mut self) -> Outputs<'_> {
+        Outputs {
+            comp: self,
+            input: None,
+            done: false,
+        }
+    }
+
+    pub fn outputs_with_input<'a, F>(&'a mut self, input: F) -> Outputs<'a>
+    where
+        F: FnMut() -> i64 + 'a,
+    {
+        Outputs {
+            comp: self,
+            input: Some(Box::new(input)),
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Outputs<'a> {
+    type Item = Result<i64, Fault>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        self.comp.run_as_coroutine();
+        loop {
+            match self.comp.status {
+                Status::ProducedOutput(out) => return Some(Ok(out)),
+                Status::RequiresInput => match self.input {
+                    Some(ref mut input) => {
+                        let value = input();
+                        self.comp.send_input(value);
+                    }
+                    None => self.comp.end_input(),
+                },
+                Status::Halted => {
+                    self.done = true;
+                    return None;
+                }
+                Status::Faulted(ref fault) => {
+                    self.done = true;
+                    return Some(Err(fault.clone()));
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Outputs<'a> {
+    /// Outputs grouped by two, e.g. (paint, turn) commands of the
+    /// painting robot.
+    pub fn pairs(self) -> impl Iterator<Item = Result<(i64, i64), Fault>> + 'a {
+        self.groups(2).map(|group| group.map(|g| (g[0], g[1])))
+    }
+
+    /// Outputs grouped by three, e.g. (x, y, tile) draw commands of the
+    /// arcade cabinet.
+    pub fn triples(self) -> impl Iterator<Item = Result<(i64, i64, i64), Fault>> + 'a {
+        self.groups(3)
+            .map(|group| group.map(|g| (g[0], g[1], g[2])))
+    }
+
+    fn groups(mut self, size: usize) -> impl Iterator<Item = Result<Vec<i64>, Fault>> + 'a {
+        iter::from_fn(move || {
+            let mut group = Vec::with_capacity(size);
+            while group.len() < size {
+                match self.next() {
+                    Some(Ok(out)) => group.push(out),
+                    Some(Err(fault)) => return Some(Err(fault)),
+                    None if group.is_empty() => return None,
+                    None => return Some(Err(Fault::IncompleteOutput { values: group })),
+                }
+            }
+            Some(Ok(group))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Reads a number and outputs it back doubled, until it reads zero.
+    const DOUBLER: &[i64] = &[
+        3, 15, 1006, 15, 14, 1002, 15, 2, 16, 4, 16, 1105, 1, 0, 99, 0, 0,
+    ];
+
+    #[test]
+    fn test_outputs_with_input() {
+        let mut comp = Computer::new();
+        comp.load_memory(DOUBLER);
+        let mut inputs = vec![3, 2, 1, 0].into_iter();
+        let outs: Result<Vec<_>, _> = comp.outputs_with_input(|| inputs.next().unwrap()).collect();
+        assert_eq!(outs, Ok(vec![6, 4, 2]));
+    }
+
+    #[test]
+    fn test_outputs_without_input() {
+        let mut comp = Computer::new();
+        comp.load_memory(DOUBLER);
+        let outs: Vec<_> = comp.outputs().collect();
+        assert_eq!(outs, vec![Err(Fault::NoInput { ip: 0 })]);
+        assert_eq!(comp.status, Status::Faulted(Fault::NoInput { ip: 0 }));
+    }
+
+    #[test]
+    fn test_groups() {
+        let mut comp = Computer::new();
+        comp.load_memory(vec![104, 1, 104, 2, 104, 3, 104, 4, 104, 5, 99]);
+        let pairs: Vec<_> = comp.outputs().pairs().collect();
+        assert_eq!(
+            pairs,
+            vec![
+                Ok((1, 2)),
+                Ok((3, 4)),
+                Err(Fault::IncompleteOutput { values: vec![5] })
+            ]
+        );
+
+        comp.load_memory(vec![104, 1, 104, 2, 104, 3, 99]);
+        let triples: Vec<_> = comp.outputs().triples().collect();
+        assert_eq!(triples, vec![Ok((1, 2, 3))]);
+    }
+}
@@ -0,0 +1,143 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+const HEADER: &str = "intcode-patch v1";
+
+/// Highest address a patch may write to, so that a typo in a patch file
+/// can't make memory grow without bound.
+pub const MAX_ADDRESS: usize = (1 << 20) - 1;
+
+/// Memory words to overwrite before a program is run, written as
+/// `address=value` pairs, e.g. `1=12, 2=2`.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct Patch {
+    pub writes: Vec<(usize, i64)>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Patch::default()
+    }
+
+    pub fn set(mut self, addr: usize, value: i64) -> Self {
+        self.writes.push((addr, value));
+        self
+    }
+
+    /// Saves the patch as a file, one `address=value` pair per line
+    /// after a header line. Lines starting with `#` are comments.
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        let mut text = format!("{}\n", HEADER);
+        for (addr, value) in self.writes.iter() {
+            text.push_str(&format!("{}={}\n", addr, value));
+        }
+        fs::write(path, text)
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse_file(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse_file(s: &str) -> Result<Self, String> {
+        let mut lines = s
+            .lines()
+            .map(str::trim)
+            .filter(|l| !l.is_empty() && !l.starts_with('#'));
+        if lines.next() != Some(HEADER) {
+            return Err(format!("patch should start with '{}'", HEADER));
+        }
+        lines.collect::<Vec<_>>().join("\n").parse()
+    }
+
+    /// Applies the writes in order, memory is extended with zeroes if a
+    /// write goes past its end. Fails without writing anything if an
+    /// address is past `MAX_ADDRESS`.
+    pub fn apply(&self, mem: &mut Vec<i64>) -> Result<(), String> {
+        if let Some(&(addr, _)) = self.writes.iter().find(|&&(addr, _)| addr > MAX_ADDRESS) {
+            return Err(format!(
+                "patch writes to address {}, past the last one allowed ({})",
+                addr, MAX_ADDRESS
+            ));
+        }
+        for &(addr, value) in self.writes.iter() {
+            if addr >= mem.len() {
+                mem.resize(addr + 1, 0);
+            }
+            mem[addr] = value;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Patch {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut patch = Patch::new();
+        for write in s.split(|c: char| c == ',' || c.is_whitespace()) {
+            if write.is_empty() {
+                continue;
+            }
+            let (addr, value) = write
+                .split_once('=')
+                .ok_or_else(|| format!("expected address=value: {}", write))?;
+            let addr = addr
+                .parse()
+                .map_err(|e| format!("bad address in '{}': {}", write, e))?;
+            let value = value
+                .parse()
+                .map_err(|e| format!("bad value in '{}': {}", write, e))?;
+            patch = patch.set(addr, value);
+        }
+        Ok(patch)
+    }
+}
+
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (ix, (addr, value)) in self.writes.iter().enumerate() {
+            if ix > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}={}", addr, value)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_patch() {
+        let patch: Patch = "1=12, 2=2 6=-1".parse().unwrap();
+        assert_eq!(patch, Patch::new().set(1, 12).set(2, 2).set(6, -1));
+        assert_eq!(patch.to_string(), "1=12, 2=2, 6=-1");
+
+        let mut mem = vec![1, 0, 0, 3];
+        assert_eq!(patch.apply(&mut mem), Ok(()));
+        assert_eq!(mem, vec![1, 12, 2, 3, 0, 0, -1]);
+
+        let huge = Patch::new().set(0, 7).set(MAX_ADDRESS + 1, 1);
+        assert!(huge.apply(&mut mem).is_err());
+        assert_eq!(mem, vec![1, 12, 2, 3, 0, 0, -1]);
+        assert_eq!(Patch::new().set(MAX_ADDRESS, 1).apply(&mut vec![]), Ok(()));
+
+        assert!("1:12".parse::<Patch>().is_err());
+
+        let file = Patch::parse_file("intcode-patch v1\n# noun and verb\n1=12\n2=2\n");
+        assert_eq!(file, Ok(Patch::new().set(1, 12).set(2, 2)));
+        assert!(Patch::parse_file("1=12\n").is_err());
+    }
+}
@@ -0,0 +1,233 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::*;
+
+const HEADER: &str = "intcode-session v1";
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Event {
+    Input(i64),
+    Output(i64),
+    Halt,
+}
+
+/// Inputs and outputs of a single program run, in the order they
+/// happened.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Session {
+    pub program_hash: u64,
+    pub events: Vec<Event>,
+}
+
+/// The first point where a replayed run stopped matching the session.
+#[derive(PartialEq, Eq, Debug)]
+pub enum Divergence {
+    Program {
+        expected: u64,
+        actual: u64,
+    },
+    Event {
+        index: usize,
+        ip: usize,
+        expected: Event,
+        actual: Status,
+    },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Divergence::Program { expected, actual } => write!(
+                f,
+                "session was recorded for program {:016x}, but {:016x} is loaded",
+                expected, actual
+            ),
+            Divergence::Event {
+                index,
+                ip,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "event #{} at address {}: expected {:?}, but the computer is in {:?}",
+                index, ip, expected, actual
+            ),
+        }
+    }
+}
+
+impl Session {
+    pub fn new(program_hash: u64) -> Self {
+        Session {
+            program_hash,
+            events: vec![],
+        }
+    }
+
+    pub fn save<P>(&self, path: P) -> io::Result<()>
+    where
+        P: AsRef<Path>,
+    {
+        fs::write(path, self.to_string())
+    }
+
+    pub fn load<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        Self::parse(&fs::read_to_string(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let mut lines = s.lines().map(str::trim).filter(|l| !l.is_empty());
+        if lines.next() != Some(HEADER) {
+            return Err(format!("session should start with '{}'", HEADER));
+        }
+
+        let program_hash = match lines.next().and_then(|l| l.split_once(' ')) {
+            Some(("program", hash)) => u64::from_str_radix(hash.trim(), 16)
+                .map_err(|e| format!("bad program hash: {}", e))?,
+            _ => return Err("program hash is missing".to_owned()),
+        };
+
+        let mut events = vec![];
+        for line in lines {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            let value = || -> Result<i64, String> {
+                parts
+                    .get(1)
+                    .ok_or_else(|| format!("missing value: {}", line))?
+                    .parse()
+                    .map_err(|e| format!("bad value in '{}': {}", line, e))
+            };
+            events.push(match parts[0] {
+                "in" => Event::Input(value()?),
+                "out" => Event::Output(value()?),
+                "halt" => Event::Halt,
+                _ => return Err(format!("unknown event: {}", line)),
+            });
+        }
+
+        Ok(Session {
+            program_hash,
+            events,
+        })
+    }
+
+    /// Runs the program loaded into `comp` feeding it the recorded
+    /// inputs and checking that it produces the recorded outputs.
+    /// Returns the number of replayed events.
+    pub fn replay(&self, comp: &mut Computer) -> Result<usize, Divergence> {
+        let actual = program_hash(comp.dump_memory());
+        if actual != self.program_hash {
+            return Err(Divergence::Program {
+                expected: self.program_hash,
+                actual,
+            });
+        }
+
+        comp.run_as_coroutine();
+        for (index, event) in self.events.iter().enumerate() {
+            match (&comp.status, event) {
+                (Status::ProducedOutput(out), Event::Output(expected)) if out == expected => {
+                    comp.run_as_coroutine();
+                }
+                (Status::RequiresInput, Event::Input(value)) => comp.send_input(*value),
+                (Status::Halted, Event::Halt) => {}
+                (status, _) => {
+                    return Err(Divergence::Event {
+                        index,
+                        ip: comp.ip,
+                        expected: event.clone(),
+                        actual: status.clone(),
+                    })
+                }
+            }
+        }
+
+        Ok(self.events.len())
+    }
+}
+
+impl fmt::Display for Session {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", HEADER)?;
+        writeln!(f, "program {:016x}", self.program_hash)?;
+        for event in &self.events {
+            match event {
+                Event::Input(value) => writeln!(f, "in {}", value)?,
+                Event::Output(value) => writeln!(f, "out {}", value)?,
+                Event::Halt => writeln!(f, "halt")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Outputs the sum of two inputs.
+    const ADDER: &[i64] = &[3, 11, 3, 12, 1, 11, 12, 13, 4, 13, 99, 0, 0, 0];
+
+    fn record(inputs: &[i64]) -> Session {
+        let mut comp = Computer::new();
+        comp.load_memory(ADDER);
+        comp.start_recording();
+        comp.run_as_coroutine();
+        for input in inputs {
+            comp.send_input(*input);
+        }
+        comp.run_as_coroutine();
+        comp.stop_recording().unwrap()
+    }
+
+    #[test]
+    fn test_record_and_replay() {
+        let session = record(&[3, 4]);
+        assert_eq!(
+            session.events,
+            vec![
+                Event::Input(3),
+                Event::Input(4),
+                Event::Output(7),
+                Event::Halt
+            ]
+        );
+        assert_eq!(Session::parse(&session.to_string()), Ok(session.clone()));
+
+        let mut comp = Computer::new();
+        comp.load_memory(ADDER);
+        assert_eq!(session.replay(&mut comp), Ok(4));
+    }
+
+    #[test]
+    fn test_divergence() {
+        let mut session = record(&[3, 4]);
+        session.events[2] = Event::Output(8);
+
+        let mut comp = Computer::new();
+        comp.load_memory(ADDER);
+        assert_eq!(
+            session.replay(&mut comp),
+            Err(Divergence::Event {
+                index: 2,
+                ip: 10,
+                expected: Event::Output(8),
+                actual: Status::ProducedOutput(7),
+            })
+        );
+
+        let mut comp = Computer::new();
+        comp.load_memory(&ADDER[1..]);
+        match session.replay(&mut comp) {
+            Err(Divergence::Program { .. }) => {}
+            other => panic!("unexpected replay result: {:?}", other),
+        }
+    }
+}
@@ -1,7 +1,15 @@
 #![feature(is_sorted)]
 #![feature(toowned_clone_into)]
 
+pub mod answer;
+pub mod expected;
+pub mod history;
+pub mod inputs;
 pub mod intcode;
+pub mod ocr;
+pub mod parallel;
+pub mod solvers;
+pub mod timing;
 
 pub mod day1;
 pub mod day2;
@@ -17,3 +25,4 @@ pub mod day10;
 pub mod day11;
 pub mod day12;
 pub mod day13;
+pub mod day13_draw;
@@ -1,91 +1,1132 @@
+use aoc_2019::answer::Answer;
+use aoc_2019::expected::{Expected, Outcome};
+use aoc_2019::history::{self, History};
+use aoc_2019::inputs::{self, InputSet};
+use aoc_2019::intcode::bundle::Registry;
+use aoc_2019::intcode::diff;
+use aoc_2019::intcode::patch::Patch;
+use aoc_2019::intcode::{Computer, Limits, Status};
 use aoc_2019::*;
 
+use clap::{App, AppSettings, Arg, ArgMatches, SubCommand};
 use env_logger;
+use std::collections::VecDeque;
+use std::env;
+use std::fs;
 use std::io;
+use std::io::BufRead;
+use std::io::BufReader;
+use std::io::Read;
+use std::io::Write;
+use std::path::Path;
+use std::process;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Side of the square a bitmap pixel becomes in saved pictures.
+const PNG_SCALE: u32 = 10;
+
+/// Size of an arcade tile in recorded frames.
+const ARCADE_SCALE: u32 = 4;
+/// Every few frames in a GIF, the whole game takes thousands of them.
+const GIF_STEP: usize = 4;
+/// Hundredths of a second per GIF frame.
+const GIF_DELAY: u16 = 3;
 
 fn main() -> io::Result<()> {
     env_logger::init();
-    println!("day 13, part 1: {}", day13::solve_part1());
+    let matches = App::new("aoc-2019")
+        .setting(AppSettings::VersionlessSubcommands)
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .subcommand(
+            SubCommand::with_name("run")
+                .about("Solves puzzles and prints the answers")
+                .arg(
+                    Arg::with_name("DAY")
+                        .required_unless("all")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("PART")
+                        .help("Solves both parts if not given")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("all")
+                        .long("all")
+                        .help("Solves all days")
+                        .conflicts_with_all(&["DAY", "PART"]),
+                )
+                .arg(
+                    Arg::with_name("parallel")
+                        .long("parallel")
+                        .help("Solves all days at once on a pool of threads")
+                        .requires("all"),
+                )
+                .arg(
+                    Arg::with_name("jobs")
+                        .short("j")
+                        .long("jobs")
+                        .value_name("N")
+                        .help("Number of threads, one per CPU if not given")
+                        .validator(is_number)
+                        .requires("parallel"),
+                )
+                .arg(
+                    Arg::with_name("timeout")
+                        .long("timeout")
+                        .value_name("SECS")
+                        .help("Gives up on a part which takes longer")
+                        .validator(is_number)
+                        .requires("parallel"),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .value_name("PATH")
+                        .help("Reads the puzzle input from a file, '-' is stdin")
+                        .conflicts_with("all"),
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .help("Prints every answer as a line of JSON"),
+                )
+                .arg(
+                    Arg::with_name("png")
+                        .long("png")
+                        .value_name("DIR")
+                        .help("Saves picture answers as dayN-partM.png in DIR"),
+                )
+                .arg(
+                    Arg::with_name("play")
+                        .long("play")
+                        .help("Plays the day 13 arcade game, moves are read from stdin")
+                        .conflicts_with_all(&["all", "PART", "json", "png", "record"]),
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .long("record")
+                        .value_name("PATH")
+                        .help(
+                            "Records the day 13 autopilot as a GIF if PATH ends with .gif, \
+                             otherwise as PNG frames in the PATH directory",
+                        )
+                        .conflicts_with("all"),
+                ),
+        )
+        .subcommand(SubCommand::with_name("list").about("Lists solved days and parts"))
+        .subcommand(
+            SubCommand::with_name("verify")
+                .about("Solves puzzles and compares the answers with confirmed ones")
+                .arg(
+                    Arg::with_name("DAY")
+                        .help("Verifies all days if not given")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("PART")
+                        .help("Verifies both parts if not given")
+                        .validator(is_number),
+                )
+                .args(&input_args())
+                .arg(all_profiles_arg())
+                .arg(
+                    Arg::with_name("answers")
+                        .long("answers")
+                        .value_name("PATH")
+                        .help("File with confirmed answers")
+                        .default_value("input/answers.txt"),
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .long("record")
+                        .help("Saves answers which aren't confirmed yet as confirmed"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("bench-all")
+                .about("Times parsing and solving of every day and part")
+                .args(&input_args())
+                .arg(all_profiles_arg())
+                .arg(
+                    Arg::with_name("warmup")
+                        .long("warmup")
+                        .value_name("N")
+                        .help("Runs before measuring")
+                        .default_value("1")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("repetitions")
+                        .short("n")
+                        .long("repetitions")
+                        .value_name("N")
+                        .help("Measured runs")
+                        .default_value("5")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("json")
+                        .long("json")
+                        .value_name("PATH")
+                        .help("Saves the timings as JSON, '-' is stdout"),
+                )
+                .arg(
+                    Arg::with_name("csv")
+                        .long("csv")
+                        .value_name("PATH")
+                        .help("Saves the timings as CSV, '-' is stdout"),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("perf")
+                .about("Times all days and compares them with an earlier revision")
+                .arg(
+                    Arg::with_name("baseline")
+                        .short("b")
+                        .long("baseline")
+                        .value_name("REV")
+                        .help("Recorded revision to compare with"),
+                )
+                .arg(
+                    Arg::with_name("record")
+                        .long("record")
+                        .help("Stores the timings under the current revision"),
+                )
+                .arg(
+                    Arg::with_name("list")
+                        .long("list")
+                        .help("Lists revisions recorded on this machine")
+                        .conflicts_with_all(&["baseline", "record"]),
+                )
+                .arg(
+                    Arg::with_name("threshold")
+                        .short("t")
+                        .long("threshold")
+                        .value_name("PERCENT")
+                        .help("Slowdown which counts as a regression")
+                        .default_value("10")
+                        .validator(is_number),
+                )
+                .arg(
+                    Arg::with_name("history")
+                        .long("history")
+                        .value_name("PATH")
+                        .help("File with recorded timings")
+                        .default_value("perf-history.txt"),
+                )
+                .arg(
+                    Arg::with_name("revision")
+                        .long("revision")
+                        .value_name("REV")
+                        .help("Revision to record as, the git HEAD if not given"),
+                )
+                .arg(
+                    Arg::with_name("machine")
+                        .long("machine")
+                        .value_name("NAME")
+                        .help("Machine to record as, the host name if not given"),
+                )
+                .args(&input_args())
+                .arg(
+                    Arg::with_name("repetitions")
+                        .short("n")
+                        .long("repetitions")
+                        .value_name("N")
+                        .help("Measured runs, after one warmup run")
+                        .default_value("5")
+                        .validator(is_number),
+                ),
+        )
+        .subcommand(
+            SubCommand::with_name("intcode")
+                .about("Runs an Intcode program")
+                .arg(
+                    Arg::with_name("PROGRAM")
+                        .help("File with a comma separated Intcode program or a name with --bundle")
+                        .required(true),
+                )
+                .arg(
+                    Arg::with_name("bundle")
+                        .short("b")
+                        .long("bundle")
+                        .value_name("PATH")
+                        .help("Looks PROGRAM up by name in a program bundle"),
+                )
+                .arg(
+                    Arg::with_name("patch")
+                        .short("p")
+                        .long("patch")
+                        .value_name("NAME")
+                        .help("Applies a named patch from the bundle")
+                        .requires("bundle")
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("input")
+                        .short("i")
+                        .long("input")
+                        .value_name("VALUES")
+                        .help("Comma separated input values, read before any input file")
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("input-file")
+                        .short("f")
+                        .long("input-file")
+                        .value_name("PATH")
+                        .help("Reads input from a file, '-' is stdin")
+                        .conflicts_with("no-stdin"),
+                )
+                .arg(
+                    Arg::with_name("ascii")
+                        .short("a")
+                        .long("ascii")
+                        .help("Prints outputs as ASCII and reads input files as text"),
+                )
+                .arg(
+                    Arg::with_name("apply-patch")
+                        .long("apply-patch")
+                        .value_name("PATH")
+                        .help("Applies a patch file before running")
+                        .multiple(true)
+                        .number_of_values(1),
+                )
+                .arg(
+                    Arg::with_name("diff")
+                        .long("diff")
+                        .help("Shows memory changed by the run with disassembly"),
+                )
+                .arg(
+                    Arg::with_name("save-diff")
+                        .long("save-diff")
+                        .value_name("PATH")
+                        .help("Saves memory changed by the run as a patch file"),
+                )
+                .arg(
+                    Arg::with_name("dump-memory")
+                        .long("dump-memory")
+                        .value_name("PATH")
+                        .help("Writes memory after the program stops, '-' is stdout"),
+                )
+                .arg(
+                    Arg::with_name("max-steps")
+                        .long("max-steps")
+                        .value_name("N")
+                        .help("Stops with a fault after N instructions"),
+                )
+                .arg(
+                    Arg::with_name("usage")
+                        .long("usage")
+                        .help("Prints resource usage to stderr"),
+                )
+                .arg(
+                    Arg::with_name("no-stdin")
+                        .long("no-stdin")
+                        .help("Faults instead of reading stdin when inputs run out"),
+                ),
+        )
+        .get_matches();
+
+    match matches.subcommand() {
+        ("run", Some(m)) => process::exit(run(m)),
+        ("list", Some(_)) => list(),
+        ("verify", Some(m)) => process::exit(verify(m)?),
+        ("bench-all", Some(m)) => process::exit(bench_all(m)?),
+        ("perf", Some(m)) => process::exit(perf(m)?),
+        ("intcode", Some(m)) => process::exit(intcode(m).unwrap_or_else(|e| {
+            eprintln!("error: {}", e);
+            1
+        })),
+        _ => unreachable!(),
+    }
+    Ok(())
+}
+
+/// Arguments selecting an input set.
+fn input_args<'a, 'b>() -> [Arg<'a, 'b>; 2] {
+    [
+        Arg::with_name("inputs")
+            .long("inputs")
+            .value_name("DIR")
+            .help("Directory with dayN.txt inputs, and input sets in subdirectories")
+            .default_value("input"),
+        Arg::with_name("profile")
+            .short("p")
+            .long("profile")
+            .value_name("NAME")
+            .help("Input set to use, AOC_PROFILE or 'default' if not given"),
+    ]
+}
+
+fn all_profiles_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("all-profiles")
+        .long("all-profiles")
+        .help("Runs on every input set in the inputs directory")
+        .conflicts_with("profile")
+}
+
+/// Prints an error for every set which doesn't exist, returns whether
+/// there was any.
+fn missing_sets(sets: &[InputSet]) -> bool {
+    let mut missing = false;
+    for set in sets.iter().filter(|set| !set.exists()) {
+        eprintln!(
+            "error: input set {} doesn't exist, there's no {}",
+            set.name,
+            set.dir.display()
+        );
+        missing = true;
+    }
+    missing
+}
+
+/// Input sets selected by `input_args` and `all_profiles_arg`.
+fn input_sets(m: &ArgMatches) -> io::Result<Vec<InputSet>> {
+    let root = m.value_of("inputs").unwrap();
+    if m.is_present("all-profiles") {
+        inputs::all(root)
+    } else {
+        Ok(vec![InputSet::selected(root, m.value_of("profile"))])
+    }
+}
+
+fn is_number(s: String) -> Result<(), String> {
+    s.parse::<u32>()
+        .map(|_| ())
+        .map_err(|_| format!("expected a number: {}", s))
+}
+
+/// Returns the exit code: 1 if any of the requested parts can't be
+/// solved or an input can't be read.
+fn run(m: &ArgMatches) -> i32 {
+    let set = InputSet::selected(m.value_of("inputs").unwrap(), m.value_of("profile"));
+    // An input given by path doesn't need the set.
+    if m.value_of("input").is_none() && missing_sets(std::slice::from_ref(&set)) {
+        return 1;
+    }
+    if m.is_present("parallel") {
+        return run_parallel(m, &set);
+    }
+    if m.is_present("all") {
+        let mut code = 0;
+        for day in solvers::days() {
+            let input = match set.read(day.day) {
+                Ok(input) => input,
+                Err(e) => {
+                    eprintln!("error: can't read input of day {}: {}", day.day, e);
+                    code = 1;
+                    continue;
+                }
+            };
+            for part in 1..=2 {
+                if let Err(e) = print_answer(m, day.day, part, &day.solve(part, &input).unwrap()) {
+                    eprintln!("error: {}", e);
+                    code = 1;
+                }
+            }
+        }
+        return code;
+    }
+
+    let day = m.value_of("DAY").unwrap().parse().unwrap();
+    let parts = match m.value_of("PART") {
+        Some(part) => vec![part.parse().unwrap()],
+        None => vec![1, 2],
+    };
+    let day = match solvers::find(day) {
+        Ok(day) => day,
+        Err(e) => {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+    };
+    let input = match read_input(m.value_of("input"), &set, day.day) {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: can't read input of day {}: {}", day.day, e);
+            return 1;
+        }
+    };
+    if m.is_present("play") || m.is_present("record") {
+        if let Err(e) = arcade(m, day.day, &input) {
+            eprintln!("error: {}", e);
+            return 1;
+        }
+        if m.is_present("play") {
+            return 0;
+        }
+    }
+    let mut code = 0;
+    for part in parts {
+        let printed = day
+            .solve(part, &input)
+            .map_err(|e| e.to_string())
+            .and_then(|answer| print_answer(m, day.day, part, &answer).map_err(|e| e.to_string()));
+        if let Err(e) = printed {
+            eprintln!("error: {}", e);
+            code = 1;
+        }
+    }
+    code
+}
+
+/// Solves all days like `run --all` but on a pool of threads. Returns the
+/// exit code: 1 if a part fails, times out or an input can't be read.
+fn run_parallel(m: &ArgMatches, set: &InputSet) -> i32 {
+    let threads = match m.value_of("jobs") {
+        Some(jobs) => jobs.parse().unwrap(),
+        None => thread::available_parallelism().map_or(1, |n| n.get()),
+    };
+    let options = parallel::Options {
+        threads,
+        timeout: m
+            .value_of("timeout")
+            .map(|secs| Duration::from_secs(secs.parse().unwrap())),
+    };
+
+    let mut code = 0;
+    let mut jobs = vec![];
+    for day in solvers::days() {
+        match set.read(day.day) {
+            Ok(input) => jobs.extend((1..=2).map(|part| parallel::Job {
+                day: day.day,
+                part,
+                input: input.clone(),
+            })),
+            Err(e) => {
+                eprintln!("error: can't read input of day {}: {}", day.day, e);
+                code = 1;
+            }
+        }
+    }
+    parallel::run(jobs, options, |report| {
+        let failure = match report.outcome {
+            parallel::Outcome::Solved { answer, .. } => {
+                print_answer(m, report.day, report.part, &answer)
+                    .err()
+                    .map(|e| e.to_string())
+            }
+            parallel::Outcome::Failed(e) => Some(e),
+            parallel::Outcome::TimedOut(timeout) => Some(format!("timed out after {:?}", timeout)),
+        };
+        if let Some(e) = failure {
+            eprintln!("error: day {}, part {}: {}", report.day, report.part, e);
+            code = 1;
+        }
+    });
+    code
+}
+
+/// Plays or records the day 13 arcade game.
+fn arcade(m: &ArgMatches, day: u32, input: &str) -> Result<(), String> {
+    if day != 13 {
+        return Err("only day 13 has an arcade game".to_owned());
+    }
+    let program = Computer::try_parse_program(input)?;
+    if m.is_present("play") {
+        println!("score: {}", day13::play(&program));
+        return Ok(());
+    }
+
+    let path = m.value_of("record").unwrap();
+    let recorder = day13_draw::record_autopilot(&program, ARCADE_SCALE);
+    let saved = if path.ends_with(".gif") {
+        recorder.save_gif(path, GIF_STEP, GIF_DELAY)
+    } else {
+        fs::create_dir_all(path)
+            .map_err(|e| e.into())
+            .and_then(|_| recorder.save_png_sequence(path))
+    };
+    saved.map_err(|e| format!("can't save the recording to {}: {}", path, e))?;
+    eprintln!("recorded {} frames to {}", recorder.frames().len(), path);
+    Ok(())
+}
+
+/// Reads the input from `path`, stdin if it's `-`, or from the input
+/// set.
+fn read_input(path: Option<&str>, set: &InputSet, day: u32) -> io::Result<String> {
+    match path {
+        Some("-") => {
+            let mut input = String::new();
+            io::stdin().read_to_string(&mut input)?;
+            Ok(input)
+        }
+        Some(path) => fs::read_to_string(path),
+        None => set.read(day),
+    }
+}
+
+/// Answers spanning several lines, like bitmaps, start on a line of
+/// their own.
+fn print_answer(m: &ArgMatches, day: u32, part: u32, answer: &Answer) -> io::Result<()> {
+    if let (Some(dir), Answer::Bitmap(bitmap)) = (m.value_of("png"), answer) {
+        let path = Path::new(dir).join(format!("day{}-part{}.png", day, part));
+        bitmap.save_png(path, PNG_SCALE)?;
+    }
+    let text = answer.to_string();
+    if m.is_present("json") {
+        println!(
+            "{{\"day\":{},\"part\":{},\"answer\":{}}}",
+            day,
+            part,
+            answer.to_json()
+        );
+    } else if text.contains('\n') {
+        print!("day {}, part {}:\n{}", day, part, text);
+    } else {
+        println!("day {}, part {}: {}", day, part, text);
+    }
     Ok(())
 }
 
+fn list() {
+    for day in solvers::days() {
+        println!("day {:>2}: parts 1, 2", day.day);
+    }
+}
+
+/// Returns the exit code: 1 if any answer is wrong or a day can't be
+/// solved.
+fn verify(m: &ArgMatches) -> io::Result<i32> {
+    let sets = input_sets(m)?;
+    if missing_sets(&sets) {
+        return Ok(1);
+    }
+    let answers = m.value_of("answers").unwrap();
+    let mut expected = Expected::load(answers)?;
+
+    let days = match m.value_of("DAY") {
+        Some(day) => match solvers::find(day.parse().unwrap()) {
+            Ok(day) => vec![day],
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return Ok(1);
+            }
+        },
+        None => solvers::days(),
+    };
+    let parts = match m.value_of("PART") {
+        Some(part) => vec![part.parse().unwrap()],
+        None => vec![1, 2],
+    };
+
+    let (mut passed, mut failed, mut missing, mut recorded) = (0, 0, 0, 0);
+    let start = Instant::now();
+    for set in sets.iter() {
+        if sets.len() > 1 {
+            println!("input set {}:", set.name);
+        }
+        let name = &set.name;
+        for day in days.iter() {
+            let input = match read_set_input(set, day.day) {
+                Ok(Some(input)) => input,
+                Ok(None) => continue,
+                Err(e) => {
+                    eprintln!("error: can't read input of day {}: {}", day.day, e);
+                    failed += parts.len();
+                    continue;
+                }
+            };
+            for &part in parts.iter() {
+                let solved = Instant::now();
+                let answer = match day.solve(part, &input) {
+                    Ok(answer) => answer,
+                    Err(e) => {
+                        eprintln!("error: {}", e);
+                        failed += 1;
+                        continue;
+                    }
+                };
+                let time = solved.elapsed();
+                let outcome = expected.check(day.day, part, name, &answer);
+                let shown = match answer {
+                    Answer::Bitmap(ref bitmap) => {
+                        format!("{}x{} bitmap", bitmap.width, bitmap.height)
+                    }
+                    _ => answer.to_string(),
+                };
+                println!(
+                    "day {:>2}, part {}: {:>12?}  {}  {}",
+                    day.day, part, time, shown, outcome
+                );
+                match outcome {
+                    Outcome::Pass => passed += 1,
+                    Outcome::Fail { .. } => failed += 1,
+                    Outcome::Missing if m.is_present("record") => {
+                        match expected.record(day.day, part, name, &answer) {
+                            Ok(()) => recorded += 1,
+                            Err(e) => eprintln!("error: {}", e),
+                        }
+                        missing += 1;
+                    }
+                    Outcome::Missing => missing += 1,
+                }
+            }
+        }
+    }
+    println!(
+        "{} passed, {} failed, {} missing in {:?}",
+        passed,
+        failed,
+        missing,
+        start.elapsed()
+    );
+    if recorded > 0 {
+        expected.save(answers)?;
+        println!("recorded {} answers in {}", recorded, answers);
+    }
+    Ok(if failed > 0 { 1 } else { 0 })
+}
+
+/// Returns the exit code: 1 if an input can't be read.
+fn bench_all(m: &ArgMatches) -> io::Result<i32> {
+    let sets = input_sets(m)?;
+    if missing_sets(&sets) {
+        return Ok(1);
+    }
+    let options = timing::Options {
+        warmup: m.value_of("warmup").unwrap().parse().unwrap(),
+        repetitions: m.value_of("repetitions").unwrap().parse().unwrap(),
+    };
+
+    let mut code = 0;
+    let mut tables = String::new();
+    let mut timings = vec![];
+    for set in sets.iter() {
+        let (set_timings, set_code) = time_all(set, options);
+        if sets.len() > 1 {
+            tables.push_str(&format!("input set {}:\n", set.name));
+        }
+        tables.push_str(&timing::report(&set_timings));
+        timings.extend(set_timings);
+        code = code.max(set_code);
+    }
+
+    let exports = [
+        (m.value_of("json"), timing::to_json(&timings)),
+        (m.value_of("csv"), timing::to_csv(&timings)),
+    ];
+    // The tables would get in the way of an export to stdout.
+    if exports.iter().all(|(path, _)| *path != Some("-")) {
+        print!("{}", tables);
+    }
+    for (path, text) in exports.iter() {
+        match path {
+            Some("-") => print!("{}", text),
+            Some(path) => fs::write(path, text)?,
+            None => (),
+        }
+    }
+    Ok(code)
+}
+
+/// Timings of both parts of all days with an input in `set`, and the
+/// exit code: 1 if an input can't be read.
+fn time_all(set: &InputSet, options: timing::Options) -> (Vec<timing::Timing>, i32) {
+    let mut code = 0;
+    let mut timings = vec![];
+    for day in solvers::days() {
+        let input = match read_set_input(set, day.day) {
+            Ok(Some(input)) => input,
+            Ok(None) => continue,
+            Err(e) => {
+                eprintln!("error: can't read input of day {}: {}", day.day, e);
+                code = 1;
+                continue;
+            }
+        };
+        for part in 1..=2 {
+            timings.push(timing::time(&day, part, &set.name, &input, options).unwrap());
+        }
+    }
+    (timings, code)
+}
+
+/// Input of `day` in `set`, `None` if the set has none. Not everyone
+/// has solved every day, so a missing input is only noted. The set
+/// itself is expected to exist, see `missing_sets`.
+fn read_set_input(set: &InputSet, day: u32) -> io::Result<Option<String>> {
+    match set.read(day) {
+        Ok(input) => Ok(Some(input)),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound => {
+            eprintln!("note: input set {} has no input for day {}", set.name, day);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Returns the exit code: 1 if a day got slower than the threshold or
+/// an input can't be read.
+fn perf(m: &ArgMatches) -> io::Result<i32> {
+    let path = m.value_of("history").unwrap();
+    let mut history = History::load(path)?;
+    let machine = match m.value_of("machine") {
+        Some(machine) => machine.to_owned(),
+        None => machine_name(),
+    };
+    if m.is_present("list") {
+        for revision in history.revisions(&machine) {
+            println!("{}", revision);
+        }
+        return Ok(0);
+    }
+
+    let options = timing::Options {
+        warmup: 1,
+        repetitions: m.value_of("repetitions").unwrap().parse().unwrap(),
+    };
+    let set = InputSet::selected(m.value_of("inputs").unwrap(), m.value_of("profile"));
+    if missing_sets(std::slice::from_ref(&set)) {
+        return Ok(1);
+    }
+    // Checked before timing, which takes a while.
+    if let Some(baseline) = m.value_of("baseline") {
+        if history
+            .records(baseline, &machine)
+            .all(|r| r.profile != set.name)
+        {
+            eprintln!(
+                "error: revision {} isn't recorded on {} with input set {}",
+                baseline, machine, set.name
+            );
+            return Ok(1);
+        }
+    }
+
+    let (timings, mut code) = time_all(&set, options);
+    match m.value_of("baseline") {
+        Some(baseline) => {
+            let threshold = m.value_of("threshold").unwrap().parse::<f64>().unwrap();
+            let changes =
+                history::compare(history.records(baseline, &machine), &timings, threshold);
+            print!("{}", history::report(&changes));
+            let slower = changes.iter().filter(|c| c.slower).count();
+            if slower > 0 {
+                println!(
+                    "{} slower than {} by more than {}%",
+                    slower, baseline, threshold
+                );
+                code = 1;
+            }
+        }
+        None => print!("{}", timing::report(&timings)),
+    }
+
+    if m.is_present("record") {
+        let revision = match m.value_of("revision") {
+            Some(revision) => revision.to_owned(),
+            None => git_revision()?,
+        };
+        history
+            .add(&revision, &machine, &timings)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        history.save(path)?;
+        println!("recorded as {} on {}", revision, machine);
+    }
+    Ok(code)
+}
+
+/// Short hash of HEAD, with `+dirty` if tracked files are changed.
+fn git_revision() -> io::Result<String> {
+    let git = |args: &[&str]| -> io::Result<String> {
+        let output = process::Command::new("git").args(args).output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "git {} failed, use --revision",
+                args.join(" ")
+            )));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_owned())
+    };
+    let mut revision = git(&["rev-parse", "--short", "HEAD"])?;
+    if !git(&["status", "--porcelain", "--untracked-files=no"])?.is_empty() {
+        revision.push_str("+dirty");
+    }
+    Ok(revision)
+}
+
+fn machine_name() -> String {
+    fs::read_to_string("/etc/hostname")
+        .ok()
+        .or_else(|| env::var("HOSTNAME").ok())
+        .or_else(|| env::var("COMPUTERNAME").ok())
+        .map(|name| name.trim().to_owned())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_owned())
+}
+
+/// Input values for the `intcode` command: values given on the command
+/// line first, then ones read lazily from a file or stdin.
+struct Inputs {
+    queue: VecDeque<i64>,
+    reader: Option<Box<dyn BufRead>>,
+    ascii: bool,
+}
+
+impl Inputs {
+    fn next(&mut self) -> io::Result<Option<i64>> {
+        while self.queue.is_empty() {
+            let reader = match self.reader {
+                Some(ref mut reader) => reader,
+                None => return Ok(None),
+            };
+            let mut line = String::new();
+            if reader.read_line(&mut line)? == 0 {
+                self.reader = None;
+            } else if self.ascii {
+                self.queue.extend(line.bytes().map(i64::from));
+            } else {
+                self.queue.extend(parse_values(&line)?);
+            }
+        }
+        Ok(self.queue.pop_front())
+    }
+}
+
+fn parse_values(s: &str) -> io::Result<Vec<i64>> {
+    s.split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("bad input value: {}", v),
+                )
+            })
+        })
+        .collect()
+}
+
+/// Prefixes the message of `e` with the file it's about.
+fn with_path(path: &str, e: io::Error) -> io::Error {
+    io::Error::new(e.kind(), format!("{}: {}", path, e))
+}
+
+fn load_program(path: &str) -> Result<Vec<i64>, String> {
+    let text =
+        fs::read_to_string(path).map_err(|e| format!("can't read program {}: {}", path, e))?;
+    Computer::try_parse_program(&text).map_err(|e| format!("{}: {}", path, e))
+}
+
+/// Returns the exit code: 0 if the program halted, 1 on a fault.
+fn intcode(m: &ArgMatches) -> io::Result<i32> {
+    let mut comp = Computer::new();
+    let program = m.value_of("PROGRAM").unwrap();
+    match m.value_of("bundle") {
+        Some(bundle) => {
+            let patches: Vec<&str> = m.values_of("patch").into_iter().flatten().collect();
+            let code = Registry::load(bundle)
+                .map_err(|e| with_path(bundle, e))?
+                .program(program, &patches)
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            comp.load_memory(code);
+        }
+        None => match load_program(program) {
+            Ok(code) => comp.load_memory(code),
+            Err(e) => {
+                eprintln!("error: {}", e);
+                return Ok(1);
+            }
+        },
+    }
+    let mut initial = comp.dump_memory().to_vec();
+    for path in m.values_of("apply-patch").into_iter().flatten() {
+        Patch::load(path)
+            .and_then(|patch| {
+                patch
+                    .apply(&mut initial)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+            })
+            .map_err(|e| with_path(path, e))?;
+        comp.load_memory(&initial);
+    }
+    if let Some(steps) = m.value_of("max-steps") {
+        let steps = steps.parse().map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "--max-steps should be a number",
+            )
+        })?;
+        comp.set_limits(Limits {
+            instructions: Some(steps),
+            ..Limits::default()
+        });
+    }
+
+    let ascii = m.is_present("ascii");
+    let mut queue = VecDeque::new();
+    for values in m.values_of("input").into_iter().flatten() {
+        queue.extend(parse_values(values)?);
+    }
+    let reader: Option<Box<dyn BufRead>> = match m.value_of("input-file") {
+        Some("-") => Some(Box::new(BufReader::new(io::stdin()))),
+        Some(path) => Some(Box::new(BufReader::new(
+            fs::File::open(path).map_err(|e| with_path(path, e))?,
+        ))),
+        None if m.is_present("no-stdin") => None,
+        None => Some(Box::new(BufReader::new(io::stdin()))),
+    };
+    let mut inputs = Inputs {
+        queue,
+        reader,
+        ascii,
+    };
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    comp.run_as_coroutine();
+    loop {
+        match comp.status {
+            Status::ProducedOutput(value) => {
+                match value {
+                    0..=127 if ascii => write!(out, "{}", value as u8 as char)?,
+                    _ if ascii => writeln!(out, "\n{}", value)?,
+                    _ => writeln!(out, "{}", value)?,
+                }
+                comp.run_as_coroutine();
+            }
+            Status::RequiresInput => {
+                out.flush()?;
+                match inputs.next()? {
+                    Some(value) => comp.send_input(value),
+                    None => comp.end_input(),
+                }
+            }
+            Status::Halted | Status::Faulted(_) => break,
+        }
+    }
+    out.flush()?;
+
+    if m.is_present("diff") {
+        let report = diff::report(&initial, comp.dump_memory(), comp.instruction_set(), 2);
+        write!(out, "{}", report)?;
+    }
+    if let Some(path) = m.value_of("save-diff") {
+        diff::to_patch(&diff::diff(&initial, comp.dump_memory())).save(path)?;
+    }
+    if let Some(path) = m.value_of("dump-memory") {
+        let memory = comp
+            .dump_memory()
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        if path == "-" {
+            writeln!(out, "{}", memory)?;
+        } else {
+            fs::write(path, memory + "\n")?;
+        }
+    }
+    if m.is_present("usage") {
+        eprintln!("{}", comp.usage());
+    }
+    match comp.status {
+        Status::Faulted(ref fault) => {
+            eprintln!("fault: {}", fault);
+            Ok(1)
+        }
+        _ => Ok(0),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    /// Checks both parts against the confirmed answers for our inputs.
+    fn verify(day: u32) {
+        let expected = Expected::load("input/answers.txt").unwrap();
+        let input = InputSet::new("input", inputs::DEFAULT).read(day).unwrap();
+        for part in 1..=2 {
+            let answer = solvers::solve(day, part, &input).unwrap();
+            assert_eq!(
+                expected.check(day, part, inputs::DEFAULT, &answer),
+                Outcome::Pass,
+                "day {}, part {}",
+                day,
+                part
+            );
+        }
+    }
+
+    #[test]
+    fn test_load_program() {
+        assert_eq!(load_program("input/day2.txt").unwrap()[..4], [1, 0, 0, 3]);
+        let missing = load_program("input/day99.txt").unwrap_err();
+        assert!(missing.starts_with("can't read program input/day99.txt"));
+        assert!(load_program("input/day1.txt").is_err());
+    }
+
     #[test]
     fn day1_solution() {
-        assert_eq!(day1::solve_part1(), 3296269);
-        assert_eq!(day1::solve_part2(), 4941547);
+        verify(1);
     }
 
     #[test]
     fn day2_solution() {
-        assert_eq!(day2::solve_part1(), 3166704);
-        assert_eq!(day2::solve_part2(), 8018);
+        verify(2);
     }
 
     #[test]
     fn day3_solution() {
-        assert_eq!(day3::solve_part1(), 855);
-        assert_eq!(day3::solve_part2(), 11238);
+        verify(3);
     }
 
     #[test]
     #[ignore] // It's too long!
     fn day4_solution() {
-        assert_eq!(day4::solve_part1(), 1764);
-        assert_eq!(day4::solve_part2(), 1196);
+        verify(4);
     }
 
     #[test]
     fn day5_solution() {
-        assert_eq!(day5::solve_part1(), 15097178);
-        assert_eq!(day5::solve_part2(), 1558663);
+        verify(5);
     }
 
     #[test]
     fn day6_solution() {
-        assert_eq!(day6::solve_part1(), 223251);
-        assert_eq!(day6::solve_part2(), 430);
+        verify(6);
     }
 
     #[test]
     fn day7_solution() {
-        assert_eq!(day7::solve_part1(), 30940);
-        assert_eq!(day7::solve_part2(), 76211147);
+        verify(7);
     }
 
     #[test]
     fn day8_solution() {
-        assert_eq!(day8::solve_part1(), 1206);
+        verify(8);
     }
 
     #[test]
     fn day9_solution() {
-        assert_eq!(day9::solve_part1(), 3533056970);
-        assert_eq!(day9::solve_part2(), 72852);
+        verify(9);
     }
 
     #[test]
     fn day10_solution() {
-        assert_eq!(day10::solve_part1(), 221);
-        assert_eq!(day10::solve_part2(), 806);
+        verify(10);
     }
 
     #[test]
     fn day11_solution() {
-        assert_eq!(day11::solve_part1(), 2056);
+        verify(11);
     }
 
     #[test]
     fn day12_solution() {
-        assert_eq!(day12::solve_part1(), 7988);
-        assert_eq!(day12::solve_part2(), 337721412394184);
+        verify(12);
     }
 
     #[test]
     fn day13_solution() {
-        assert_eq!(day13::solve_part1(), 432);
+        verify(13);
     }
 }
@@ -0,0 +1,223 @@
+//! Reads letters drawn by puzzles in the Advent of Code block font, the
+//! 6 pixels high one and the 10 pixels high one.
+//!
+//! Letters are told apart by empty columns between them, so they may be
+//! of any width and at any offset.
+
+use std::fmt;
+
+use log::*;
+
+use crate::answer::{Answer, Bitmap};
+
+#[rustfmt::skip]
+const FONT_6: &[(char, &[&str])] = &[
+    ('A', &[".##.", "#..#", "#..#", "####", "#..#", "#..#"]),
+    ('B', &["###.", "#..#", "###.", "#..#", "#..#", "###."]),
+    ('C', &[".##.", "#..#", "#...", "#...", "#..#", ".##."]),
+    ('E', &["####", "#...", "###.", "#...", "#...", "####"]),
+    ('F', &["####", "#...", "###.", "#...", "#...", "#..."]),
+    ('G', &[".##.", "#..#", "#...", "#.##", "#..#", ".###"]),
+    ('H', &["#..#", "#..#", "####", "#..#", "#..#", "#..#"]),
+    ('I', &["###", ".#.", ".#.", ".#.", ".#.", "###"]),
+    ('J', &["..##", "...#", "...#", "...#", "#..#", ".##."]),
+    ('K', &["#..#", "#.#.", "##..", "#.#.", "#.#.", "#..#"]),
+    ('L', &["#...", "#...", "#...", "#...", "#...", "####"]),
+    ('O', &[".##.", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('P', &["###.", "#..#", "#..#", "###.", "#...", "#..."]),
+    ('R', &["###.", "#..#", "#..#", "###.", "#.#.", "#..#"]),
+    ('S', &[".###", "#...", "#...", ".##.", "...#", "###."]),
+    ('U', &["#..#", "#..#", "#..#", "#..#", "#..#", ".##."]),
+    ('Y', &["#...#", "#...#", ".#.#.", "..#..", "..#..", "..#.."]),
+    ('Z', &["####", "...#", "..#.", ".#..", "#...", "####"]),
+];
+
+#[rustfmt::skip]
+const FONT_10: &[(char, &[&str])] = &[
+    ('A', &["..##..", ".#..#.", "#....#", "#....#", "#....#",
+            "######", "#....#", "#....#", "#....#", "#....#"]),
+    ('B', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#....#", "#....#", "#....#", "#....#", "#####."]),
+    ('C', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#....#", ".####."]),
+    ('E', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('F', &["######", "#.....", "#.....", "#.....", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('G', &[".####.", "#....#", "#.....", "#.....", "#.....",
+            "#..###", "#....#", "#....#", "#...##", ".###.#"]),
+    ('H', &["#....#", "#....#", "#....#", "#....#", "######",
+            "#....#", "#....#", "#....#", "#....#", "#....#"]),
+    ('J', &["...###", "....#.", "....#.", "....#.", "....#.",
+            "....#.", "....#.", "#...#.", "#...#.", ".###.."]),
+    ('K', &["#....#", "#...#.", "#..#..", "#.#...", "##....",
+            "##....", "#.#...", "#..#..", "#...#.", "#....#"]),
+    ('L', &["#.....", "#.....", "#.....", "#.....", "#.....",
+            "#.....", "#.....", "#.....", "#.....", "######"]),
+    ('N', &["#....#", "##...#", "##...#", "#.#..#", "#.#..#",
+            "#..#.#", "#..#.#", "#...##", "#...##", "#....#"]),
+    ('P', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#.....", "#.....", "#.....", "#.....", "#....."]),
+    ('R', &["#####.", "#....#", "#....#", "#....#", "#####.",
+            "#..#..", "#...#.", "#...#.", "#....#", "#....#"]),
+    ('X', &["#....#", "#....#", ".#..#.", ".#..#.", "..##..",
+            "..##..", ".#..#.", ".#..#.", "#....#", "#....#"]),
+    ('Z', &["######", ".....#", ".....#", "....#.", "...#..",
+            "..#...", ".#....", "#.....", "#.....", "######"]),
+];
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Error {
+    /// Nothing is lit.
+    Empty,
+    /// The lit rows don't match the height of any font.
+    UnknownHeight(usize),
+    /// Columns where the glyphs which aren't letters start, and the text
+    /// with `?` in their place.
+    UnknownGlyphs { columns: Vec<usize>, text: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Empty => write!(f, "no letters, the bitmap is empty"),
+            Error::UnknownHeight(height) => {
+                write!(f, "letters should be 6 or 10 pixels high, not {}", height)
+            }
+            Error::UnknownGlyphs { columns, text } => write!(
+                f,
+                "unknown glyphs at columns {:?}, read '{}'",
+                columns, text
+            ),
+        }
+    }
+}
+
+/// Reads the letters in `bitmap`.
+pub fn recognize(bitmap: &Bitmap) -> Result<String, Error> {
+    let lit_rows: Vec<usize> = (0..bitmap.height)
+        .filter(|&y| (0..bitmap.width).any(|x| bitmap.get(x, y)))
+        .collect();
+    let (top, bottom) = match (lit_rows.first(), lit_rows.last()) {
+        (Some(&top), Some(&bottom)) => (top, bottom + 1),
+        _ => return Err(Error::Empty),
+    };
+    let font = match bottom - top {
+        6 => FONT_6,
+        10 => FONT_10,
+        height => return Err(Error::UnknownHeight(height)),
+    };
+
+    let lit_column = |x: usize| (top..bottom).any(|y| bitmap.get(x, y));
+    let mut text = String::new();
+    let mut columns = vec![];
+    let mut x = 0;
+    while x < bitmap.width {
+        if !lit_column(x) {
+            x += 1;
+            continue;
+        }
+        let start = x;
+        while x < bitmap.width && lit_column(x) {
+            x += 1;
+        }
+        let glyph: Vec<String> = (top..bottom)
+            .map(|y| {
+                (start..x)
+                    .map(|x| if bitmap.get(x, y) { '#' } else { '.' })
+                    .collect()
+            })
+            .collect();
+        match font.iter().find(|(_, rows)| trim(rows) == glyph) {
+            Some(&(letter, _)) => text.push(letter),
+            None => {
+                text.push('?');
+                columns.push(start);
+            }
+        }
+    }
+
+    if columns.is_empty() {
+        Ok(text)
+    } else {
+        Err(Error::UnknownGlyphs { columns, text })
+    }
+}
+
+/// The letters in `bitmap` as a text answer, or the bitmap itself if
+/// they can't be read.
+pub fn answer(bitmap: Bitmap) -> Answer {
+    match recognize(&bitmap) {
+        Ok(text) => Answer::Text(text),
+        Err(e) => {
+            warn!("can't read the letters: {}", e);
+            Answer::Bitmap(bitmap)
+        }
+    }
+}
+
+/// Glyph rows without the empty columns on the sides.
+fn trim(rows: &[&str]) -> Vec<String> {
+    let width = rows[0].len();
+    let lit = |x: usize| rows.iter().any(|r| r.as_bytes()[x] == b'#');
+    let start = (0..width).find(|&x| lit(x)).unwrap_or(0);
+    let end = (0..width).rev().find(|&x| lit(x)).map_or(0, |x| x + 1);
+    rows.iter().map(|r| r[start..end].to_owned()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Draws the whole font in a row, letters a column apart.
+    fn alphabet(font: &[(char, &[&str])]) -> (String, Bitmap) {
+        let height = font[0].1.len();
+        let mut rows = vec![String::new(); height];
+        for (_, glyph) in font {
+            for (row, glyph_row) in rows.iter_mut().zip(glyph.iter()) {
+                row.push_str(&glyph_row.replace('.', " "));
+                row.push(' ');
+            }
+        }
+        let rows: Vec<&str> = rows.iter().map(String::as_str).collect();
+        let letters = font.iter().map(|(letter, _)| letter).collect();
+        (letters, Bitmap::from_rows(&rows))
+    }
+
+    #[test]
+    fn test_alphabet() {
+        let (letters, bitmap) = alphabet(FONT_6);
+        assert_eq!(letters, "ABCEFGHIJKLOPRSUYZ");
+        assert_eq!(recognize(&bitmap), Ok(letters));
+
+        let (letters, bitmap) = alphabet(FONT_10);
+        assert_eq!(letters, "ABCEFGHJKLNPRXZ");
+        assert_eq!(recognize(&bitmap), Ok(letters));
+    }
+
+    #[test]
+    fn test_unknown() {
+        #[rustfmt::skip]
+        let bitmap = Bitmap::from_rows(&[
+            "",
+            " #  #  #   ##   ",
+            " #  #  #  #  #  ",
+            " #### ### #  #  ",
+            " #  #  #  #  #  ",
+            " #  #  #  #  #  ",
+            " #  #  #   ##   ",
+        ]);
+        assert_eq!(
+            recognize(&bitmap),
+            Err(Error::UnknownGlyphs {
+                columns: vec![6],
+                text: "H?O".to_owned()
+            })
+        );
+        assert_eq!(answer(bitmap.clone()), Answer::Bitmap(bitmap));
+
+        assert_eq!(recognize(&Bitmap::new(3, 3)), Err(Error::Empty));
+        let tall = Bitmap::from_rows(&["#", "#", "#"]);
+        assert_eq!(recognize(&tall), Err(Error::UnknownHeight(3)));
+    }
+}
@@ -0,0 +1,256 @@
+//! Solves many puzzle parts at once on a pool of threads.
+//!
+//! Solvers are isolated from each other: a panic is reported as a
+//! failure of its part, and a part which runs for longer than the
+//! timeout is reported as timed out. Threads can't be stopped, so one
+//! stuck in a timed out solver is left running and a new thread takes
+//! its place in the pool.
+
+use std::any::Any;
+use std::panic;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::answer::Answer;
+use crate::solvers;
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Job {
+    pub day: u32,
+    pub part: u32,
+    pub input: String,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Options {
+    pub threads: usize,
+    pub timeout: Option<Duration>,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Outcome {
+    Solved {
+        answer: Answer,
+        time: Duration,
+    },
+    /// The solver panicked or the day or part doesn't exist.
+    Failed(String),
+    TimedOut(Duration),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Report {
+    pub day: u32,
+    pub part: u32,
+    pub outcome: Outcome,
+}
+
+enum Message {
+    Started(usize),
+    Done(usize, Outcome),
+}
+
+type Jobs = Arc<Mutex<Receiver<(usize, Job)>>>;
+type Solve = Arc<dyn Fn(&Job) -> Result<Answer, String> + Send + Sync>;
+
+/// Runs `jobs` and calls `report` for each of them in the order of
+/// `jobs`, as soon as it and all the jobs before it are done.
+pub fn run<F>(jobs: Vec<Job>, options: Options, report: F)
+where
+    F: FnMut(Report),
+{
+    let solve =
+        |job: &Job| solvers::solve(job.day, job.part, &job.input).map_err(|e| e.to_string());
+    run_with(jobs, options, Arc::new(solve), report);
+}
+
+/// Like `run` but solves jobs with `solve`.
+fn run_with<F>(jobs: Vec<Job>, options: Options, solve: Solve, mut report: F)
+where
+    F: FnMut(Report),
+{
+    let (job_sender, job_receiver) = mpsc::channel();
+    let keys: Vec<(u32, u32)> = jobs.iter().map(|j| (j.day, j.part)).collect();
+    for job in jobs.into_iter().enumerate() {
+        job_sender.send(job).unwrap();
+    }
+    drop(job_sender);
+    let job_receiver = Arc::new(Mutex::new(job_receiver));
+
+    let (sender, receiver) = mpsc::channel();
+    for _ in 0..options.threads.max(1).min(keys.len()) {
+        spawn_worker(&job_receiver, &sender, &solve);
+    }
+
+    let mut outcomes: Vec<Option<Outcome>> = vec![None; keys.len()];
+    let mut started: Vec<Option<Instant>> = vec![None; keys.len()];
+    let mut next = 0;
+    while next < keys.len() {
+        let deadline = options.timeout.and_then(|timeout| {
+            (0..keys.len())
+                .filter(|&ix| outcomes[ix].is_none())
+                .filter_map(|ix| started[ix])
+                .min()
+                .map(|start| start + timeout)
+        });
+        let message = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match message {
+            Ok(Message::Started(ix)) => started[ix] = Some(Instant::now()),
+            Ok(Message::Done(ix, outcome)) => {
+                if ix >= next && outcomes[ix].is_none() {
+                    outcomes[ix] = Some(outcome);
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let timeout = options.timeout.unwrap();
+                for ix in 0..keys.len() {
+                    let overdue = started[ix].is_some_and(|start| start.elapsed() >= timeout);
+                    if outcomes[ix].is_none() && overdue {
+                        outcomes[ix] = Some(Outcome::TimedOut(timeout));
+                        spawn_worker(&job_receiver, &sender, &solve);
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Disconnected) => unreachable!("the pool keeps a sender"),
+        }
+
+        while next < keys.len() && outcomes[next].is_some() {
+            let (day, part) = keys[next];
+            let outcome = outcomes[next].take().unwrap();
+            // Not running any more, so no deadline to keep.
+            started[next] = None;
+            report(Report { day, part, outcome });
+            next += 1;
+        }
+    }
+}
+
+fn spawn_worker(jobs: &Jobs, sender: &Sender<Message>, solve: &Solve) {
+    let (jobs, sender, solve) = (Arc::clone(jobs), sender.clone(), Arc::clone(solve));
+    thread::spawn(move || loop {
+        let job = jobs.lock().unwrap().recv();
+        let (ix, job) = match job {
+            Ok(job) => job,
+            Err(_) => return,
+        };
+        if sender.send(Message::Started(ix)).is_err() {
+            return;
+        }
+        let outcome = isolate(&job, &solve);
+        if sender.send(Message::Done(ix, outcome)).is_err() {
+            return;
+        }
+    });
+}
+
+/// Solves `job`, turning a panic into a failure.
+fn isolate(job: &Job, solve: &Solve) -> Outcome {
+    let start = Instant::now();
+    let solved = panic::catch_unwind(panic::AssertUnwindSafe(|| solve(job)));
+    match solved {
+        Ok(Ok(answer)) => Outcome::Solved {
+            answer,
+            time: start.elapsed(),
+        },
+        Ok(Err(e)) => Outcome::Failed(e),
+        Err(payload) => Outcome::Failed(format!("panicked: {}", panic_message(&payload))),
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_owned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(day: u32, part: u32, input: &str) -> Job {
+        Job {
+            day,
+            part,
+            input: input.to_owned(),
+        }
+    }
+
+    #[test]
+    fn test_run() {
+        let jobs = vec![
+            job(4, 1, "100000-400000"),
+            job(1, 2, "1969"),
+            job(1, 1, "twelve"),
+            job(26, 1, ""),
+            job(1, 1, "12"),
+        ];
+        let options = Options {
+            threads: 3,
+            timeout: None,
+        };
+        let mut reports = vec![];
+        run(jobs, options, |report| reports.push(report));
+
+        let keys: Vec<(u32, u32)> = reports.iter().map(|r| (r.day, r.part)).collect();
+        assert_eq!(keys, vec![(4, 1), (1, 2), (1, 1), (26, 1), (1, 1)]);
+        let answers: Vec<Option<&Answer>> = reports
+            .iter()
+            .map(|r| match r.outcome {
+                Outcome::Solved { ref answer, .. } => Some(answer),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(answers[1], Some(&Answer::Integer(966)));
+        assert_eq!(answers[4], Some(&Answer::Integer(2)));
+        match reports[2].outcome {
+            Outcome::Failed(ref message) => assert!(message.contains("InvalidDigit")),
+            ref outcome => panic!("expected a failure, got {:?}", outcome),
+        }
+        assert_eq!(
+            reports[3].outcome,
+            Outcome::Failed("day 26 is not solved yet".to_owned())
+        );
+    }
+
+    #[test]
+    fn test_timeout() {
+        // Day 0 blocks until the test is over, others answer right away.
+        let (release, blocked) = mpsc::channel::<()>();
+        let blocked = Mutex::new(blocked);
+        let solve = move |job: &Job| {
+            if job.day == 0 {
+                let _ = blocked.lock().unwrap().recv();
+            }
+            Ok(Answer::from(job.day))
+        };
+        let options = Options {
+            threads: 1,
+            timeout: Some(Duration::from_millis(50)),
+        };
+        let mut reports = vec![];
+        let jobs = vec![job(1, 1, ""), job(0, 1, ""), job(2, 1, "")];
+        run_with(jobs, options, Arc::new(solve), |report| {
+            reports.push(report.outcome)
+        });
+        drop(release);
+
+        assert_eq!(reports[1], Outcome::TimedOut(Duration::from_millis(50)));
+        // The stuck thread was replaced, so the last job still ran.
+        match reports[2] {
+            Outcome::Solved { ref answer, .. } => assert_eq!(*answer, Answer::Integer(2)),
+            ref outcome => panic!("expected an answer, got {:?}", outcome),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+//! Puzzle solutions and a registry of them by day.
+
+use std::any::Any;
+use std::fmt;
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+use crate::answer::Answer;
+use crate::*;
+
+/// A day's puzzle. The input text is parsed once and shared by both
+/// parts.
+pub trait Solution {
+    type Input;
+    type Part1: Into<Answer>;
+    type Part2: Into<Answer>;
+
+    fn parse(input: &str) -> Self::Input;
+    fn part1(input: &Self::Input) -> Self::Part1;
+    fn part2(input: &Self::Input) -> Self::Part2;
+}
+
+/// Object safe view of a `Solution`, parsed input is passed around as
+/// `Any` and answers are converted to `Answer`.
+pub trait Solver {
+    fn parse(&self, input: &str) -> Box<dyn Any>;
+    fn part1(&self, input: &dyn Any) -> Answer;
+    fn part2(&self, input: &dyn Any) -> Answer;
+}
+
+struct Erased<T>(PhantomData<T>);
+
+impl<T> Solver for Erased<T>
+where
+    T: Solution,
+    T::Input: 'static,
+{
+    fn parse(&self, input: &str) -> Box<dyn Any> {
+        Box::new(T::parse(input))
+    }
+
+    fn part1(&self, input: &dyn Any) -> Answer {
+        T::part1(input.downcast_ref().expect("input of another day")).into()
+    }
+
+    fn part2(&self, input: &dyn Any) -> Answer {
+        T::part2(input.downcast_ref().expect("input of another day")).into()
+    }
+}
+
+pub fn solver<T>() -> Box<dyn Solver>
+where
+    T: Solution + 'static,
+{
+    Box::new(Erased::<T>(PhantomData))
+}
+
+pub struct Day {
+    pub day: u32,
+    pub solver: Box<dyn Solver>,
+}
+
+impl Day {
+    pub fn solve(&self, part: u32, input: &str) -> Result<Answer, Error> {
+        let input = self.solver.parse(input);
+        match part {
+            1 => Ok(self.solver.part1(input.as_ref())),
+            2 => Ok(self.solver.part2(input.as_ref())),
+            _ => Err(Error::UnknownPart(part)),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub enum Error {
+    UnknownDay(u32),
+    UnknownPart(u32),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::UnknownDay(day) => write!(f, "day {} is not solved yet", day),
+            Error::UnknownPart(part) => write!(f, "there is no part {}, only 1 and 2", part),
+        }
+    }
+}
+
+/// All solved days in order.
+pub fn days() -> Vec<Day> {
+    vec![
+        Day {
+            day: 1,
+            solver: solver::<day1::Day1>(),
+        },
+        Day {
+            day: 2,
+            solver: solver::<day2::Day2>(),
+        },
+        Day {
+            day: 3,
+            solver: solver::<day3::Day3>(),
+        },
+        Day {
+            day: 4,
+            solver: solver::<day4::Day4>(),
+        },
+        Day {
+            day: 5,
+            solver: solver::<day5::Day5>(),
+        },
+        Day {
+            day: 6,
+            solver: solver::<day6::Day6>(),
+        },
+        Day {
+            day: 7,
+            solver: solver::<day7::Day7>(),
+        },
+        Day {
+            day: 8,
+            solver: solver::<day8::Day8>(),
+        },
+        Day {
+            day: 9,
+            solver: solver::<day9::Day9>(),
+        },
+        Day {
+            day: 10,
+            solver: solver::<day10::Day10>(),
+        },
+        Day {
+            day: 11,
+            solver: solver::<day11::Day11>(),
+        },
+        Day {
+            day: 12,
+            solver: solver::<day12::Day12>(),
+        },
+        Day {
+            day: 13,
+            solver: solver::<day13::Day13>(),
+        },
+    ]
+}
+
+pub fn find(day: u32) -> Result<Day, Error> {
+    days()
+        .into_iter()
+        .find(|d| d.day == day)
+        .ok_or(Error::UnknownDay(day))
+}
+
+pub fn solve(day: u32, part: u32, input: &str) -> Result<Answer, Error> {
+    find(day)?.solve(part, input)
+}
+
+/// Where the input of `day` is in an inputs directory.
+pub fn input_path<P>(dir: P, day: u32) -> PathBuf
+where
+    P: AsRef<Path>,
+{
+    dir.as_ref().join(format!("day{}.txt", day))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_solve() {
+        assert_eq!(solve(1, 1, "12\n1969\n"), Ok(Answer::Integer(656)));
+        assert_eq!(solve(1, 2, "1969"), Ok(Answer::Integer(966)));
+        assert_eq!(solve(1, 3, "12"), Err(Error::UnknownPart(3)));
+        assert_eq!(solve(26, 1, ""), Err(Error::UnknownDay(26)));
+        assert_eq!(input_path("input", 3), Path::new("input/day3.txt"));
+    }
+}
@@ -0,0 +1,243 @@
+//! Timing of solvers, with input parsing and solving measured apart.
+
+use std::fmt::Write;
+use std::time::{Duration, Instant};
+
+use crate::answer::json_string;
+use crate::solvers::{Day, Error};
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub struct Options {
+    /// Runs before measuring, to warm up caches.
+    pub warmup: usize,
+    pub repetitions: usize,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            warmup: 1,
+            repetitions: 5,
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub struct Stats {
+    pub median: Duration,
+    pub min: Duration,
+    pub max: Duration,
+}
+
+impl Stats {
+    pub fn new(samples: &[Duration]) -> Self {
+        if samples.is_empty() {
+            return Stats::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort();
+        let len = sorted.len();
+        Stats {
+            // Both are the middle sample if there's an odd number of them.
+            median: (sorted[(len - 1) / 2] + sorted[len / 2]) / 2,
+            min: sorted[0],
+            max: sorted[len - 1],
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Timing {
+    /// Name of the input set.
+    pub profile: String,
+    pub day: u32,
+    pub part: u32,
+    pub parse: Stats,
+    pub solve: Stats,
+    /// Parsing and solving together.
+    pub total: Stats,
+}
+
+/// Parses `input` from the `profile` input set and solves `part` of
+/// `day` repeatedly.
+pub fn time(
+    day: &Day,
+    part: u32,
+    profile: &str,
+    input: &str,
+    options: Options,
+) -> Result<Timing, Error> {
+    if part != 1 && part != 2 {
+        return Err(Error::UnknownPart(part));
+    }
+    let (mut parse, mut solve, mut total) = (vec![], vec![], vec![]);
+    for run in 0..options.warmup + options.repetitions {
+        let start = Instant::now();
+        let parsed = day.solver.parse(input);
+        let parsed_at = Instant::now();
+        if part == 1 {
+            day.solver.part1(parsed.as_ref());
+        } else {
+            day.solver.part2(parsed.as_ref());
+        }
+        let solved_at = Instant::now();
+        if run >= options.warmup {
+            parse.push(parsed_at - start);
+            solve.push(solved_at - parsed_at);
+            total.push(solved_at - start);
+        }
+    }
+    Ok(Timing {
+        profile: profile.to_owned(),
+        day: day.day,
+        part,
+        parse: Stats::new(&parse),
+        solve: Stats::new(&solve),
+        total: Stats::new(&total),
+    })
+}
+
+/// Sums of the medians of all timings: parse, solve and total.
+pub fn totals(timings: &[Timing]) -> (Duration, Duration, Duration) {
+    timings
+        .iter()
+        .fold(Default::default(), |(p, s, t), timing| {
+            (
+                p + timing.parse.median,
+                s + timing.solve.median,
+                t + timing.total.median,
+            )
+        })
+}
+
+/// Table for the terminal, medians with the range of the total time.
+pub fn report(timings: &[Timing]) -> String {
+    let mut table = format!(
+        "{:>3} {:>4} {:>12} {:>12} {:>12} {:>12} {:>12}\n",
+        "day", "part", "parse", "solve", "total", "min", "max"
+    );
+    for t in timings {
+        writeln!(
+            table,
+            "{:>3} {:>4} {:>12?} {:>12?} {:>12?} {:>12?} {:>12?}",
+            t.day, t.part, t.parse.median, t.solve.median, t.total.median, t.total.min, t.total.max
+        )
+        .unwrap();
+    }
+    let (parse, solve, total) = totals(timings);
+    writeln!(
+        table,
+        "{:>8} {:>12?} {:>12?} {:>12?}",
+        "total", parse, solve, total
+    )
+    .unwrap();
+    table
+}
+
+const COLUMNS: [&str; 12] = [
+    "profile",
+    "day",
+    "part",
+    "parse_median_ns",
+    "parse_min_ns",
+    "parse_max_ns",
+    "solve_median_ns",
+    "solve_min_ns",
+    "solve_max_ns",
+    "total_median_ns",
+    "total_min_ns",
+    "total_max_ns",
+];
+
+/// Values of the columns after the profile.
+fn values(t: &Timing) -> Vec<u128> {
+    let mut values = vec![t.day.into(), t.part.into()];
+    for stats in [t.parse, t.solve, t.total].iter() {
+        values.extend(&[
+            stats.median.as_nanos(),
+            stats.min.as_nanos(),
+            stats.max.as_nanos(),
+        ]);
+    }
+    values
+}
+
+/// One line per timing after a header, times in nanoseconds.
+pub fn to_csv(timings: &[Timing]) -> String {
+    let mut csv = COLUMNS.join(",") + "\n";
+    for t in timings {
+        let mut row = vec![t.profile.clone()];
+        row.extend(values(t).iter().map(u128::to_string));
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Array of objects with the same fields as the CSV columns.
+pub fn to_json(timings: &[Timing]) -> String {
+    let objects: Vec<String> = timings
+        .iter()
+        .map(|t| {
+            let mut fields = vec![format!("\"{}\":{}", COLUMNS[0], json_string(&t.profile))];
+            fields.extend(
+                COLUMNS[1..]
+                    .iter()
+                    .zip(values(t))
+                    .map(|(column, value)| format!("\"{}\":{}", column, value)),
+            );
+            format!("{{{}}}", fields.join(","))
+        })
+        .collect();
+    format!("[{}]\n", objects.join(",\n "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solvers;
+
+    #[test]
+    fn test_stats() {
+        let ms = Duration::from_millis;
+        let stats = Stats::new(&[ms(5), ms(1), ms(3)]);
+        assert_eq!(
+            stats,
+            Stats {
+                median: ms(3),
+                min: ms(1),
+                max: ms(5)
+            }
+        );
+        assert_eq!(Stats::new(&[ms(4), ms(1), ms(2), ms(9)]).median, ms(3));
+        assert_eq!(Stats::new(&[]), Stats::default());
+    }
+
+    #[test]
+    fn test_time() {
+        let day = solvers::find(1).unwrap();
+        let options = Options {
+            warmup: 0,
+            repetitions: 3,
+        };
+        let timing = time(&day, 2, "mine", "1969\n", options).unwrap();
+        assert_eq!((timing.day, timing.part), (1, 2));
+        assert!(timing.total.min <= timing.total.median);
+        assert!(timing.total.max >= timing.parse.max);
+        assert_eq!(
+            time(&day, 3, "mine", "", options),
+            Err(Error::UnknownPart(3))
+        );
+
+        let csv = to_csv(std::slice::from_ref(&timing));
+        assert!(csv.starts_with("profile,day,part,parse_median_ns,"));
+        assert!(csv.lines().nth(1).unwrap().starts_with("mine,1,2,"));
+        assert!(to_json(std::slice::from_ref(&timing))
+            .starts_with("[{\"profile\":\"mine\",\"day\":1,\"part\":2,"));
+        let odd = Timing {
+            profile: "é\u{1}".to_owned(),
+            ..timing
+        };
+        assert!(to_json(&[odd]).starts_with("[{\"profile\":\"é\\u0001\","));
+    }
+}